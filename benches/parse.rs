@@ -0,0 +1,106 @@
+//! Allocations and time spent parsing long generated tracks, owned versus borrowed.
+//! Borrowing saves an allocation per text without entities, the rest are made by the deserializer itself.
+//!
+//! ```sh
+//! cargo bench --bench parse --features srv1,srv2,srv3
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use youtube_captions::format::{srv1, srv2, srv3};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(new_size, Ordering::Relaxed);
+    System.realloc(ptr, layout, new_size)
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// About three hours of speech, a line every two seconds.
+const LINES: u32 = 5_000;
+const RUNS: u32 = 10;
+
+const WORDS: [&str; 8] = ["so", "today", "we", "are", "going", "to", "talk", "about"];
+
+fn measure<T>(name: &str, parse: impl Fn() -> T) {
+  let (allocations, bytes) = (ALLOCATIONS.load(Ordering::Relaxed), BYTES.load(Ordering::Relaxed));
+  let started = Instant::now();
+  for _ in 0..RUNS {
+    black_box(parse());
+  }
+  let elapsed = started.elapsed() / RUNS;
+  let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / RUNS as usize;
+  let bytes = (BYTES.load(Ordering::Relaxed) - bytes) / RUNS as usize;
+  println!("{name:<14} {allocations:>9} allocations {:>9} KiB {elapsed:>12.2?}", bytes / 1024);
+}
+
+fn srv1() -> String {
+  let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8" ?><transcript>"#);
+  for i in 0..LINES {
+    let text = if i % 10 == 0 { "it&amp;#39;s fine" } else { "so today we are going to talk about" };
+    let _ = write!(xml, r#"<text start="{}.5" dur="2.1">{text}</text>"#, i * 2);
+  }
+  xml + "</transcript>"
+}
+
+fn srv2() -> String {
+  let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8" ?><timedtext>"#);
+  xml.push_str(r#"<window t="0" id="1" op="define" rc="2" cc="40"/>"#);
+  for i in 0..LINES {
+    let text = if i % 10 == 0 { "it&amp;#39;s fine" } else { "so today we are going to talk about" };
+    let _ = write!(xml, r#"<text t="{}" d="2000" w="1">{text}</text>"#, i * 2000);
+  }
+  xml + "</timedtext>"
+}
+
+/// Automatic captions, every word is a span.
+fn srv3() -> String {
+  let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">"#);
+  xml.push_str(r##"<head><pen id="1" fc="#E5E5E5"/><ws id="0"/><wp id="0"/></head><body>"##);
+  for i in 0..LINES {
+    let _ = write!(xml, r#"<p t="{}" d="2000">"#, i * 2000);
+    for (j, word) in WORDS.iter().enumerate() {
+      let word = if i % 10 == 0 && j == 0 { "it&amp;#39;s" } else { word };
+      let _ = write!(xml, r#"<s t="{}">{word}</s>"#, j * 250);
+    }
+    xml.push_str("</p>");
+  }
+  xml + "</body></timedtext>"
+}
+
+fn main() {
+  let document = srv1();
+  measure("srv1 owned", || document.parse::<srv1::Transcript>().unwrap());
+  measure("srv1 borrowed", || srv1::Transcript::parse(&document).unwrap());
+
+  let document = srv2();
+  measure("srv2 owned", || document.parse::<srv2::Transcript>().unwrap());
+  measure("srv2 borrowed", || srv2::Transcript::parse(&document).unwrap());
+
+  let document = srv3();
+  measure("srv3 owned", || document.parse::<srv3::Transcript>().unwrap());
+  measure("srv3 borrowed", || srv3::Transcript::parse(&document).unwrap());
+}
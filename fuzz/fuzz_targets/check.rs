@@ -0,0 +1,17 @@
+/// Strict parsing fails at the first warning of the lenient one, and the input gets to an exported document
+/// without panics, whatever the format it is detected as.
+macro_rules! check {
+  ($transcript:ty, $format:expr, $input:expr) => {{
+    let input: &str = $input;
+    let (lenient, warnings) = <$transcript>::parse_lenient(input);
+    match <$transcript>::parse(input) {
+      Ok(strict) => assert_eq!((strict, warnings.len()), (lenient, 0)),
+      Err(err) => assert_eq!(warnings.first(), Some(&err)),
+    }
+
+    youtube_captions::format::Format::detect(input);
+    if let Ok(cues) = youtube_captions::cue::Cues::parse(input, $format) {
+      youtube_captions::export::to_srt(&cues);
+    }
+  }};
+}
@@ -0,0 +1,13 @@
+//! ```sh
+//! cargo +nightly fuzz run srv1
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use youtube_captions::format::{srv1, Format};
+
+#[macro_use]
+mod check;
+
+fuzz_target!(|input: &str| check!(srv1::Transcript, Format::SRV1, input));
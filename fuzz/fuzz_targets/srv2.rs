@@ -0,0 +1,13 @@
+//! ```sh
+//! cargo +nightly fuzz run srv2
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use youtube_captions::format::{srv2, Format};
+
+#[macro_use]
+mod check;
+
+fuzz_target!(|input: &str| check!(srv2::Transcript, Format::SRV2, input));
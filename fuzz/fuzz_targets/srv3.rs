@@ -0,0 +1,13 @@
+//! ```sh
+//! cargo +nightly fuzz run srv3
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use youtube_captions::format::{srv3, Format};
+
+#[macro_use]
+mod check;
+
+fuzz_target!(|input: &str| check!(srv3::Transcript, Format::SRV3, input));
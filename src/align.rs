@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cue::{Cue, Cues};
+
+/// The longest time a single [`Pair`] may cover.
+pub const MAX_PAIR_MILLIS: u32 = 15_000;
+
+/// Text of two tracks shown at the same time, e.g. the original and its translation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pair {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  /// Empty, if the primary track has nothing at this time.
+  pub primary: String,
+  /// Empty, if the secondary track has nothing at this time.
+  pub secondary: String,
+}
+
+/// Pairs the cues of two tracks of the same video by time.
+///
+/// Tracks in different languages are often segmented differently, so a pair may join several cues of either track:
+/// cues are grouped together when at least a half of the shorter one overlaps the other.
+/// A pair never spans more than [`MAX_PAIR_MILLIS`], so rolling automatic captions don't chain into one huge pair.
+pub fn align(primary: &Cues, secondary: &Cues) -> Vec<Pair> {
+  let n = primary.len();
+  let mut groups = Groups::new(primary.iter().chain(secondary.iter()));
+
+  let mut first = 0;
+  for (i, p) in primary.iter().enumerate() {
+    while first < secondary.len() && secondary[first].end_millis() <= p.start_millis {
+      first += 1;
+    }
+    for (j, s) in secondary.iter().enumerate().skip(first).take_while(|(_, s)| s.start_millis < p.end_millis()) {
+      let overlap = p.end_millis().min(s.end_millis()).saturating_sub(p.start_millis.max(s.start_millis));
+      if overlap > 0 && overlap as u64 * 2 >= p.duration_millis.min(s.duration_millis) as u64 {
+        groups.join(i, n + j);
+      }
+    }
+  }
+
+  let mut pairs: HashMap<usize, Pair> = HashMap::new();
+  let cues = primary.iter().map(|it| (it, true)).chain(secondary.iter().map(|it| (it, false)));
+  for (i, (cue, is_primary)) in cues.enumerate() {
+    let pair = pairs.entry(groups.find(i)).or_insert_with(|| Pair::new(cue.start_millis, cue.end_millis()));
+    pair.push(cue, is_primary);
+  }
+
+  let mut pairs: Vec<_> = pairs.into_values().collect();
+  pairs.sort_by_key(|it| (it.start_millis, it.end_millis));
+  pairs
+}
+
+impl Pair {
+  fn new(start_millis: u32, end_millis: u32) -> Self {
+    Self { start_millis, end_millis, primary: String::new(), secondary: String::new() }
+  }
+
+  fn push(&mut self, cue: &Cue, is_primary: bool) {
+    self.start_millis = self.start_millis.min(cue.start_millis);
+    self.end_millis = self.end_millis.max(cue.end_millis());
+    let text = if is_primary { &mut self.primary } else { &mut self.secondary };
+    for word in cue.text.split_whitespace() {
+      if !text.is_empty() {
+        text.push(' ');
+      }
+      text.push_str(word);
+    }
+  }
+}
+
+/// Disjoint sets of cue indices, along with the time each set covers.
+struct Groups {
+  parents: Vec<usize>,
+  times: Vec<(u32, u32)>,
+}
+
+impl Groups {
+  fn new<'a>(cues: impl Iterator<Item = &'a Cue>) -> Self {
+    let times: Vec<_> = cues.map(|it| (it.start_millis, it.end_millis())).collect();
+    Self { parents: (0..times.len()).collect(), times }
+  }
+
+  fn find(&mut self, i: usize) -> usize {
+    let mut root = i;
+    while self.parents[root] != root {
+      root = self.parents[root];
+    }
+    let mut i = i;
+    while self.parents[i] != root {
+      (i, self.parents[i]) = (self.parents[i], root);
+    }
+    root
+  }
+
+  /// Keeps the sets apart, if together they would cover more than [`MAX_PAIR_MILLIS`].
+  fn join(&mut self, a: usize, b: usize) {
+    let (a, b) = (self.find(a), self.find(b));
+    let (start, end) = (self.times[a].0.min(self.times[b].0), self.times[a].1.max(self.times[b].1));
+    if a == b || end - start > MAX_PAIR_MILLIS {
+      return;
+    }
+    self.parents[a.max(b)] = a.min(b);
+    self.times[a.min(b)] = (start, end);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn differently_segmented_tracks_are_aligned() {
+    let english: Cues = vec![
+      Cue::new(0, 2000, "Hello everyone,"),
+      Cue::new(2000, 2000, "welcome to the stream."),
+      Cue::new(4000, 3000, "Today we talk about coroutines."),
+      Cue::new(9000, 1000, "(laughs)"),
+    ]
+    .into();
+    let russian: Cues = vec![
+      Cue::new(0, 4000, "Всем привет,\nдобро пожаловать на стрим."),
+      Cue::new(3900, 3000, "Сегодня поговорим о корутинах."),
+    ]
+    .into();
+
+    let pairs = align(&english, &russian);
+    let texts: Vec<_> = pairs.iter().map(|it| (it.start_millis, it.end_millis, &*it.primary, &*it.secondary)).collect();
+    assert_eq!(
+      texts,
+      [
+        (0, 4000, "Hello everyone, welcome to the stream.", "Всем привет, добро пожаловать на стрим."),
+        (3900, 7000, "Today we talk about coroutines.", "Сегодня поговорим о корутинах."),
+        (9000, 10000, "(laughs)", ""),
+      ]
+    );
+  }
+
+  #[test]
+  fn rolling_captions_are_not_chained() {
+    // every automatic cue overlaps the next one by more than a half
+    let rolling = |lang: &str| -> Cues { (0..20).map(|i| Cue::new(i * 1500, 4000, format!("{lang} {i}"))).collect() };
+
+    let pairs = align(&rolling("en"), &rolling("ru"));
+    assert!(pairs.len() > 1);
+    assert!(pairs.iter().all(|it| it.end_millis - it.start_millis <= MAX_PAIR_MILLIS));
+  }
+}
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::cue::{Cue, Cues, Span};
+
+/// A part of the caption text, that is not a speech.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Annotation {
+  /// Byte range in [`Cue::text`].
+  pub range: Range<usize>,
+  pub kind: AnnotationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AnnotationKind {
+  /// `[Music]`, `[Applause]`, `(laughs)`, without the brackets. Parentheses count only around
+  /// capitals, like `(APPLAUSE)`, or around known sounds, so spoken asides like `(I think)` are kept.
+  Sound(String),
+  /// `♪ lyrics ♪`, or just `♪` when the music is instrumental.
+  Music { lyrics: Option<String> },
+  /// `>>`, `>> JOHN:` or `- Speaker:` at the start of a line.
+  SpeakerChange { label: Option<String> },
+}
+
+lazy_static::lazy_static! {
+  static ref SOUND: Regex = Regex::new(r"\[([^\[\]\n]+)\]|\(([^()\n]+)\)").unwrap();
+  static ref SOUND_WORD: Regex = Regex::new(
+    r"(?ix)\b(?: music | laugh\w* | chuckl\w* | giggl\w* | applau\w* | cheer\w* | clap\w* | sigh\w* | cough\w*
+      | gasp\w* | groan\w* | scream\w* | sob\w* | cr(?:y|ies|ying) | whistl\w* | sniff\w* | inaudible | indistinct
+      | crosstalk | silence | static | beep\w* | bell\w* | thunder\w* | footsteps | noise )\b"
+  )
+  .unwrap();
+  static ref MUSIC: Regex = Regex::new(r"[♪♫]+(?:([^♪♫]+)[♪♫]+)?").unwrap();
+  static ref SPEAKER: Regex =
+    Regex::new(r"(?m)(?:^[ \t]*-|>>+)[ \t]*(?:(\p{Lu}[\p{L}\p{N}.'’ ]{0,30}):(?:[ \t]|$))?").unwrap();
+}
+
+impl Cue {
+  /// Finds sound events, music and speaker changes in the text, ordered by position.
+  pub fn annotations(&self) -> Vec<Annotation> {
+    let text = self.text.as_str();
+    let trimmed = |it: Option<regex::Match>| Some(it?.as_str().trim()).filter(|it| !it.is_empty()).map(String::from);
+
+    let sounds = SOUND.captures_iter(text).filter_map(|caps| {
+      let sound = match (caps.get(1), caps.get(2)) {
+        (Some(it), _) => it.as_str(),
+        (_, Some(it)) if is_sound(it.as_str()) => it.as_str(),
+        _ => return None,
+      };
+      Some((caps.get(0).unwrap().range(), AnnotationKind::Sound(sound.trim().to_owned())))
+    });
+    let music = MUSIC
+      .captures_iter(text)
+      .map(|caps| (caps.get(0).unwrap().range(), AnnotationKind::Music { lyrics: trimmed(caps.get(1)) }));
+    let speakers = SPEAKER
+      .captures_iter(text)
+      .map(|caps| (caps.get(0).unwrap().range(), AnnotationKind::SpeakerChange { label: trimmed(caps.get(1)) }));
+
+    let mut found: Vec<_> = sounds.chain(music).chain(speakers).collect();
+    found.sort_by_key(|(range, _)| (range.start, usize::MAX - range.end));
+
+    let mut annotations: Vec<Annotation> = vec![];
+    for (range, kind) in found {
+      if annotations.last().is_none_or(|it| it.range.end <= range.start) {
+        annotations.push(Annotation { range, kind });
+      }
+    }
+    annotations
+  }
+
+  /// Removes the annotations from the text and the spans, lyrics are removed together with the notes.
+  pub fn strip_annotations(&mut self) {
+    let removed: Vec<_> = self.annotations().into_iter().map(|it| it.range).collect();
+    if removed.is_empty() {
+      return;
+    }
+
+    let is_plain = self.spans.is_empty();
+    let mut spans = if is_plain {
+      vec![Span::new(0, std::mem::take(&mut self.text), Default::default())]
+    } else {
+      std::mem::take(&mut self.spans)
+    };
+
+    // drop the removed characters, then collapse the whitespaces left around them
+    let mut offset = 0;
+    let mut after_whitespace = true;
+    for span in &mut spans {
+      let start = offset;
+      offset += span.text.len();
+
+      let mut text = String::with_capacity(span.text.len());
+      for (i, c) in span.text.char_indices() {
+        if removed.iter().any(|it| it.contains(&(start + i))) {
+          continue;
+        }
+        if c.is_whitespace() && after_whitespace {
+          // line breaks win over spaces
+          if c == '\n' && text.ends_with(' ') {
+            text.pop();
+            text.push(c);
+          }
+          continue;
+        }
+        after_whitespace = c.is_whitespace();
+        text.push(c);
+      }
+      span.text = text;
+    }
+    spans.retain(|it| !it.text.is_empty());
+    if let Some(last) = spans.last_mut() {
+      last.text.truncate(last.text.trim_end().len());
+    }
+
+    self.text = spans.iter().map(|it| it.text.as_str()).collect();
+    if !is_plain {
+      self.spans = spans;
+    }
+  }
+}
+
+/// Capitals, like `APPLAUSE`, or a few words with a known sound, like `soft music playing`.
+fn is_sound(text: &str) -> bool {
+  let is_caps = text.chars().filter(|it| it.is_alphabetic()).count() > 1 && !text.chars().any(char::is_lowercase);
+  is_caps || (text.split_whitespace().count() <= 4 && SOUND_WORD.is_match(text))
+}
+
+impl Cues {
+  /// Strips the annotations from every cue, dropping the cues left without text.
+  pub fn strip_annotations(&mut self) {
+    for it in self.iter_mut() {
+      it.strip_annotations();
+    }
+    self.retain(|it| !it.text.is_empty());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::Style;
+
+  fn kinds(text: &str) -> Vec<AnnotationKind> {
+    Cue::new(0, 1000, text).annotations().into_iter().map(|it| it.kind).collect()
+  }
+
+  #[test]
+  fn annotations_are_classified() {
+    use AnnotationKind::*;
+
+    let sound = |it: &str| Sound(it.into());
+    assert_eq!(kinds("[Music]"), [sound("Music")]);
+    assert_eq!(kinds("[Applause] thank you (laughs)"), [sound("Applause"), sound("laughs")]);
+    assert_eq!(
+      kinds("♪ Take this pink ribbon\noff my eyes ♪"),
+      [Music { lyrics: Some("Take this pink ribbon\noff my eyes".into()) }]
+    );
+    assert_eq!(kinds("♪♪"), [Music { lyrics: None }]);
+    assert_eq!(
+      kinds(">> JOHN: Hi.\n>> Hello.\n- Mary Jane: Bye."),
+      [
+        SpeakerChange { label: Some("JOHN".into()) },
+        SpeakerChange { label: None },
+        SpeakerChange { label: Some("Mary Jane".into()) }
+      ]
+    );
+    assert_eq!(kinds("costs 5-10 dollars: a note"), []);
+    assert_eq!(kinds("(LAUGHTER) (soft music playing)"), [sound("LAUGHTER"), sound("soft music playing")]);
+    assert_eq!(kinds(r#"it works (I think) ("Just A Girl" - No Doubt)"#), []);
+  }
+
+  #[test]
+  fn annotations_are_stripped() {
+    let mut cues: Cues = vec![
+      Cue::new(0, 1000, "[Music]"),
+      Cue::new(1000, 1000, ">> JOHN: Hi there. (laughs)\n>> Hello (I think)."),
+      Cue::with_spans(
+        2000,
+        1000,
+        vec![
+          Span::new(0, "[Applause] ", Style::default()),
+          Span::new(100, "thank", Style { bold: true, ..Default::default() }),
+          Span::new(200, " you", Style::default()),
+        ],
+      ),
+    ]
+    .into();
+
+    cues.strip_annotations();
+    let texts: Vec<_> = cues.iter().map(|it| it.text.as_str()).collect();
+    assert_eq!(texts, ["Hi there.\nHello (I think).", "thank you"]);
+    let spans: Vec<_> = cues[1].spans.iter().map(|it| (it.offset_millis, it.text.as_str())).collect();
+    assert_eq!(spans, [(100, "thank"), (200, " you")]);
+  }
+}
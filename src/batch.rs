@@ -0,0 +1,217 @@
+use futures::{Stream, StreamExt};
+use language_tags::LanguageTag;
+
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::scraper::{CaptionScraper, Digest, DigestScraper};
+use crate::video_id::VideoId;
+
+/// Decides which caption track of a video to download.
+#[derive(Debug, Clone)]
+pub enum TrackPolicy {
+  /// A manually created track in the language, falling back to the generated one.
+  PreferManual(LanguageTag),
+  /// A manually created track in the language, generated tracks are ignored.
+  ManualOnly(LanguageTag),
+  /// A track generated using automatic speech recognition in the language.
+  GeneratedOnly(LanguageTag),
+  /// A track in the language, if there is one. Otherwise, a translatable track machine translated into it.
+  TranslateTo(LanguageTag),
+}
+
+/// Fetches captions of many videos at once, see [`DigestScraper::batch`].
+pub struct Batch<'a> {
+  scraper: &'a DigestScraper,
+  policy: TrackPolicy,
+  format: Format,
+  concurrency: usize,
+}
+
+/// The outcome for a single video. An error doesn't abort the rest of the batch.
+#[derive(Debug)]
+pub struct BatchItem {
+  /// Position of the video id in the input.
+  pub index: usize,
+  pub video_id: VideoId,
+  pub result: Result<Captions>,
+  pub progress: Progress,
+}
+
+#[derive(Debug)]
+pub struct Captions {
+  /// The track chosen by the [`TrackPolicy`].
+  pub track: CaptionScraper,
+  /// The track in the requested [`Format`].
+  pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+  /// The number of videos processed so far, including the current one.
+  pub completed: usize,
+  /// The number of videos in the batch, if the input iterator knows its length.
+  pub total: Option<usize>,
+}
+
+impl TrackPolicy {
+  pub fn select(&self, digest: &Digest) -> Result<CaptionScraper> {
+    let find = |lang: &LanguageTag, generated: Option<bool>| {
+      let mut tracks = digest.captions.iter().filter(|it| lang.matches(&it.lang_tag));
+      match generated {
+        Some(generated) => tracks.find(|it| it.is_generated == generated).cloned(),
+        None => tracks.clone().find(|it| !it.is_generated).or_else(|| tracks.next()).cloned(),
+      }
+    };
+
+    let track = match self {
+      TrackPolicy::PreferManual(lang) => find(lang, None),
+      TrackPolicy::ManualOnly(lang) => find(lang, Some(false)),
+      TrackPolicy::GeneratedOnly(lang) => find(lang, Some(true)),
+      TrackPolicy::TranslateTo(lang) => match find(lang, None) {
+        Some(track) => Some(track),
+        None if !digest.can_be_translated_to.contains(lang.as_str()) => {
+          return Err(Error::TranslationLanguageNotAvailable);
+        }
+        None => {
+          let mut tracks = digest.captions.iter().filter(|it| it.is_translatable);
+          let mut track = tracks.clone().find(|it| !it.is_generated).or_else(|| tracks.next()).cloned();
+          if let Some(track) = &mut track {
+            track.translate_to(lang)?;
+          }
+          track
+        }
+      },
+    };
+
+    track.ok_or(Error::NoTranscriptFound)
+  }
+}
+
+impl<'a> Batch<'a> {
+  pub const DEFAULT_CONCURRENCY: usize = 4;
+
+  pub(crate) fn new(scraper: &'a DigestScraper, policy: TrackPolicy, format: Format) -> Self {
+    Self { scraper, policy, format, concurrency: Self::DEFAULT_CONCURRENCY }
+  }
+
+  /// The maximum number of videos being fetched simultaneously.
+  pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+    self.concurrency = concurrency.max(1);
+    self
+  }
+
+  /// Yields the results in the order of completion, which may differ from the input order.
+  pub fn fetch<I>(&self, video_ids: I) -> impl Stream<Item = BatchItem> + '_
+  where
+    I: IntoIterator,
+    I::Item: Into<VideoId>,
+    I::IntoIter: 'a,
+  {
+    let video_ids = video_ids.into_iter();
+    let total = match video_ids.size_hint() {
+      (lower, Some(upper)) if lower == upper => Some(lower),
+      _ => None,
+    };
+
+    futures::stream::iter(video_ids.enumerate())
+      .map(move |(index, video_id)| self.fetch_one(index, video_id.into()))
+      .buffer_unordered(self.concurrency)
+      .enumerate()
+      .map(move |(done, mut item)| {
+        item.progress = Progress { completed: done + 1, total };
+        item
+      })
+  }
+
+  async fn fetch_one(&self, index: usize, video_id: VideoId) -> BatchItem {
+    let result = async {
+      let digest = self.scraper.fetch(&video_id, None).await?;
+      let track = self.policy.select(&digest)?;
+      let content = track.fetch(self.format).await?;
+      Ok(Captions { track, content })
+    };
+
+    let result = result.await;
+    BatchItem { index, video_id, result, progress: Progress { completed: 0, total: None } }
+  }
+}
+
+impl DigestScraper {
+  /// Fetches captions of many videos with bounded concurrency.
+  ///
+  /// ```no_run
+  /// # async fn run() {
+  /// use futures::StreamExt;
+  /// use youtube_captions::batch::TrackPolicy;
+  /// use youtube_captions::{format::Format, DigestScraper, VideoId};
+  ///
+  /// let scraper = DigestScraper::new(reqwest::Client::new());
+  /// let policy = TrackPolicy::PreferManual("en".parse().unwrap());
+  /// let batch = scraper.batch(policy, Format::SRV1).with_concurrency(8);
+  ///
+  /// let video_ids = ["JRMOIE_wAFk", "PHzOOQfhPFg"].map(|it| it.parse::<VideoId>().unwrap());
+  /// let mut items = batch.fetch(video_ids);
+  /// while let Some(item) = items.next().await {
+  ///   println!("{}/{:?} {}: {}", item.progress.completed, item.progress.total, item.video_id, item.result.is_ok());
+  /// }
+  /// # }
+  /// ```
+  pub fn batch(&self, policy: TrackPolicy, format: Format) -> Batch<'_> {
+    Batch::new(self, policy, format)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn track(lang: &str, is_generated: bool, is_translatable: bool) -> CaptionScraper {
+    CaptionScraper {
+      url: format!("https://www.youtube.com/api/timedtext?lang={lang}"),
+      http: reqwest::Client::new(),
+      cache: None,
+      video_id: "JRMOIE_wAFk".parse().unwrap(),
+      translated_to: None,
+      is_generated,
+      is_translatable,
+      lang_name: lang.into(),
+      lang_tag: lang.parse().unwrap(),
+    }
+  }
+
+  fn digest() -> Digest {
+    Digest {
+      video_id: "JRMOIE_wAFk".parse().unwrap(),
+      title: String::new(),
+      chapters: vec![],
+      captions: vec![track("en", true, true), track("en", false, true), track("ru", true, false)],
+      can_be_translated_to: ["de".to_owned()].into(),
+      is_live_content: false,
+    }
+  }
+
+  #[test]
+  fn manual_tracks_are_preferred() {
+    let track = TrackPolicy::PreferManual("en".parse().unwrap()).select(&digest()).unwrap();
+    assert!(!track.is_generated);
+
+    let track = TrackPolicy::PreferManual("ru".parse().unwrap()).select(&digest()).unwrap();
+    assert!(track.is_generated);
+
+    let err = TrackPolicy::ManualOnly("ru".parse().unwrap()).select(&digest()).unwrap_err();
+    assert!(matches!(err, Error::NoTranscriptFound));
+  }
+
+  #[test]
+  fn translation_is_the_last_resort() {
+    let track = TrackPolicy::TranslateTo("ru".parse().unwrap()).select(&digest()).unwrap();
+    assert_eq!(track.translated_to, None);
+
+    let track = TrackPolicy::TranslateTo("de".parse().unwrap()).select(&digest()).unwrap();
+    assert_eq!(track.translated_to, Some("de".parse().unwrap()));
+    assert!(!track.is_generated);
+
+    let err = TrackPolicy::TranslateTo("fr".parse().unwrap()).select(&digest()).unwrap_err();
+    assert!(matches!(err, Error::TranslationLanguageNotAvailable));
+  }
+}
@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::format::Format;
+use crate::video_id::VideoId;
+
+/// An on-disk cache of watch pages and caption tracks.
+///
+/// Entries are keyed by video id, language, track kind, translation target and [`Format`], so a signed
+/// caption url, which changes on every page load, never ends up in a key.
+///
+/// Fresh entries are served without touching the network. Stale caption tracks are revalidated
+/// with a conditional request (`If-None-Match` / `If-Modified-Since`), and a `304 Not Modified`
+/// response renews the entry without downloading the body again.
+///
+/// ```no_run
+/// # async fn run() -> youtube_captions::Result<()> {
+/// use std::time::Duration;
+/// use youtube_captions::{cache::Cache, DigestScraper, VideoId};
+///
+/// let cache = Cache::new(".captions").with_ttl(Duration::from_secs(6 * 60 * 60)).with_max_size(64 << 20);
+/// let scraper = DigestScraper::new(reqwest::Client::new()).with_cache(cache);
+/// let digest = scraper.fetch("JRMOIE_wAFk".parse::<VideoId>()?, None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cache {
+  dir: PathBuf,
+  ttl: Duration,
+  max_size: Option<u64>,
+}
+
+/// Identifies a cached resource.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Key<'a> {
+  pub video_id: &'a str,
+  pub lang: &'a str,
+  /// Automatic captions share the language with the manual ones.
+  pub is_generated: bool,
+  pub translate_to: Option<&'a str>,
+  /// `None` stands for the watch page.
+  pub format: Option<Format>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+  etag: Option<String>,
+  last_modified: Option<String>,
+  body: String,
+}
+
+impl Cache {
+  /// Watch pages contain signed caption urls which expire after a while,
+  /// so there is little sense in keeping them for longer.
+  pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into(), ttl: Self::DEFAULT_TTL, max_size: None }
+  }
+
+  /// How long an entry is served without asking YouTube.
+  pub fn with_ttl(mut self, ttl: Duration) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// The total size of the cache directory in bytes. The least recently stored entries are evicted first.
+  pub fn with_max_size(mut self, bytes: u64) -> Self {
+    self.max_size = Some(bytes);
+    self
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// Removes every entry related to the video: the watch page and all the caption tracks.
+  pub async fn invalidate(&self, video_id: &VideoId) -> std::io::Result<()> {
+    let prefix = format!("{}.", sanitize(video_id.as_str()));
+    for (path, _) in self.entries().await? {
+      if path.file_name().and_then(|it| it.to_str()).is_some_and(|it| it.starts_with(&prefix)) {
+        remove(&path).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Removes all the entries.
+  pub async fn clear(&self) -> std::io::Result<()> {
+    for (path, _) in self.entries().await? {
+      remove(&path).await?;
+    }
+    Ok(())
+  }
+
+  /// Returns the body, if the entry exists and has not expired yet.
+  pub(crate) async fn fresh(&self, key: &Key<'_>) -> Option<String> {
+    match self.load(key).await {
+      Some((entry, true)) => Some(entry.body),
+      _ => None,
+    }
+  }
+
+  pub(crate) async fn store(&self, key: &Key<'_>, body: &str) {
+    self.save(key, &Entry { etag: None, last_modified: None, body: body.to_owned() }).await;
+  }
+
+  /// Sends the request, unless there is a fresh entry. A stale entry is revalidated with a conditional request.
+  pub(crate) async fn fetch(&self, key: &Key<'_>, mut request: RequestBuilder) -> Result<String> {
+    let cached = match self.load(key).await {
+      Some((entry, true)) => return Ok(entry.body),
+      Some((entry, false)) => Some(entry),
+      None => None,
+    };
+
+    if let Some(entry) = &cached {
+      if let Some(etag) = &entry.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+      }
+      if let Some(last_modified) = &entry.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+      }
+    }
+
+    let res = request.send().await?;
+    if let (StatusCode::NOT_MODIFIED, Some(entry)) = (res.status(), cached) {
+      self.save(key, &entry).await;
+      return Ok(entry.body);
+    }
+
+    let res = res.error_for_status()?;
+    let header = |name| res.headers().get(name).and_then(|it| it.to_str().ok()).map(str::to_owned);
+    let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+    let entry = Entry { etag, last_modified, body: res.text().await? };
+    self.save(key, &entry).await;
+    Ok(entry.body)
+  }
+
+  fn path(&self, key: &Key<'_>) -> PathBuf {
+    let translate_to = key.translate_to.map_or_else(|| "-".into(), sanitize);
+    let kind = if key.is_generated { "asr" } else { "-" };
+    let format = key.format.map_or("watch", <&str>::from);
+    let name = format!("{}.{}.{kind}.{translate_to}.{format}.json", sanitize(key.video_id), sanitize(key.lang));
+    self.dir.join(name)
+  }
+
+  /// Returns the entry and whether it is still fresh. A broken entry is treated as a miss.
+  async fn load(&self, key: &Key<'_>) -> Option<(Entry, bool)> {
+    let path = self.path(key);
+    let modified = tokio::fs::metadata(&path).await.and_then(|it| it.modified()).ok()?;
+    let entry = serde_json::from_slice(&tokio::fs::read(&path).await.ok()?).ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    Some((entry, age < self.ttl))
+  }
+
+  /// Failing to write is not fatal: the caller already has the data, so only a warning is emitted.
+  async fn save(&self, key: &Key<'_>, entry: &Entry) {
+    let path = self.path(key);
+    let result = async {
+      tokio::fs::create_dir_all(&self.dir).await?;
+      tokio::fs::write(&path, serde_json::to_vec(entry)?).await?;
+      self.evict().await
+    };
+
+    if let Err(err) = result.await {
+      tracing::warn!("failed to cache {}: {}", path.display(), err);
+    }
+  }
+
+  async fn evict(&self) -> std::io::Result<()> {
+    let Some(max_size) = self.max_size else { return Ok(()) };
+
+    let mut entries = self.entries().await?;
+    let mut total: u64 = entries.iter().map(|(_, meta)| meta.len()).sum();
+    entries.sort_by_key(|(_, meta)| meta.modified().ok());
+
+    for (path, meta) in entries {
+      if total <= max_size {
+        break;
+      }
+      remove(&path).await?;
+      total -= meta.len();
+    }
+    Ok(())
+  }
+
+  async fn entries(&self) -> std::io::Result<Vec<(PathBuf, std::fs::Metadata)>> {
+    let mut dir = match tokio::fs::read_dir(&self.dir).await {
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+      dir => dir?,
+    };
+
+    let mut entries = vec![];
+    while let Some(it) = dir.next_entry().await? {
+      let path = it.path();
+      if path.extension().is_some_and(|ext| ext == "json") {
+        entries.push((path, it.metadata().await?));
+      }
+    }
+    Ok(entries)
+  }
+}
+
+async fn remove(path: &Path) -> std::io::Result<()> {
+  match tokio::fs::remove_file(path).await {
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    result => result,
+  }
+}
+
+/// Keeps the file name safe, whatever a caller passes as a video id.
+fn sanitize(part: &str) -> String {
+  part.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(video_id: &str, format: Option<Format>) -> Key<'_> {
+    Key { video_id, lang: "en", is_generated: false, translate_to: None, format }
+  }
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("youtube-captions-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+  }
+
+  #[tokio::test]
+  async fn entries_expire() {
+    let dir = temp_dir("expire");
+    let cache = Cache::new(&dir);
+    cache.store(&key("JRMOIE_wAFk", None), "<html>").await;
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", None)).await.as_deref(), Some("<html>"));
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", Some(Format::SRV3))).await, None);
+
+    let cache = cache.with_ttl(Duration::ZERO);
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", None)).await, None);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn invalidation_is_per_video() {
+    let dir = temp_dir("invalidate");
+    let cache = Cache::new(&dir);
+    cache.store(&key("JRMOIE_wAFk", None), "a").await;
+    cache.store(&key("JRMOIE_wAFk", Some(Format::SRV1)), "b").await;
+    cache.store(&key("PHzOOQfhPFg", None), "c").await;
+
+    cache.invalidate(&"JRMOIE_wAFk".parse().unwrap()).await.unwrap();
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", None)).await, None);
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", Some(Format::SRV1))).await, None);
+    assert_eq!(cache.fresh(&key("PHzOOQfhPFg", None)).await.as_deref(), Some("c"));
+
+    cache.clear().await.unwrap();
+    assert_eq!(cache.fresh(&key("PHzOOQfhPFg", None)).await, None);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn oldest_entries_are_evicted() {
+    let dir = temp_dir("evict");
+    let cache = Cache::new(&dir).with_max_size(150);
+    cache.store(&key("JRMOIE_wAFk", None), &"a".repeat(60)).await;
+    std::thread::sleep(Duration::from_millis(20));
+    cache.store(&key("PHzOOQfhPFg", None), &"b".repeat(60)).await;
+
+    assert_eq!(cache.fresh(&key("JRMOIE_wAFk", None)).await, None);
+    assert!(cache.fresh(&key("PHzOOQfhPFg", None)).await.is_some());
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn keys_are_sanitized() {
+    let cache = Cache::new("/tmp");
+    let key = Key {
+      video_id: "../../etc",
+      lang: "en",
+      is_generated: false,
+      translate_to: Some("de"),
+      format: Some(Format::VTT),
+    };
+    assert_eq!(cache.path(&key), Path::new("/tmp/______etc.en.-.de.vtt.json"));
+
+    let asr = Key { is_generated: true, ..key.clone() };
+    assert_eq!(cache.path(&asr), Path::new("/tmp/______etc.en.asr.de.vtt.json"));
+  }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// A color with its opacity, `a = 255` is opaque.
+///
+/// ```
+/// use youtube_captions::color::Rgba;
+///
+/// let color: Rgba = "#FFEE00".parse().unwrap();
+/// assert_eq!(color.with_opacity(128).to_css(), "#ffee0080");
+/// assert_eq!(color.to_ass(), "&H0000EEFF");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Rgba {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  pub a: u8,
+}
+
+impl Rgba {
+  pub const WHITE: Rgba = Rgba::opaque(0xff, 0xff, 0xff);
+  pub const BLACK: Rgba = Rgba::opaque(0, 0, 0);
+
+  pub const fn opaque(r: u8, g: u8, b: u8) -> Self {
+    Self { r, g, b, a: 0xff }
+  }
+
+  pub const fn with_opacity(self, a: u8) -> Self {
+    Self { a, ..self }
+  }
+
+  /// `#rrggbb`, or `#rrggbbaa` when the color is translucent.
+  pub fn to_css(&self) -> String {
+    match self.a {
+      0xff => format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b),
+      a => format!("#{:02x}{:02x}{:02x}{a:02x}", self.r, self.g, self.b),
+    }
+  }
+
+  /// `&HAABBGGRR`, where the alpha is a transparency, `00` is opaque.
+  pub fn to_ass(&self) -> String {
+    format!("&H{:02X}{:02X}{:02X}{:02X}", 0xff - self.a, self.b, self.g, self.r)
+  }
+}
+
+impl FromStr for Rgba {
+  type Err = Error;
+
+  /// Parses an opaque `#RRGGBB` color.
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    let invalid = || Error::InvalidColor(input.to_owned());
+    // `from_str_radix` alone would accept signs, like `#+f+f+f`
+    let is_hex = |it: &&str| it.len() == 6 && it.bytes().all(|it| it.is_ascii_hexdigit());
+    let hex = input.strip_prefix('#').filter(is_hex).ok_or_else(invalid)?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Self::opaque(channel(0)?, channel(2)?, channel(4)?))
+  }
+}
+
+impl fmt::Display for Rgba {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.to_css())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn colors_are_parsed() {
+    assert_eq!("#0a0B0c".parse::<Rgba>().unwrap(), Rgba::opaque(10, 11, 12));
+    for invalid in ["", "#fff", "0a0b0c", "#0a0b0g", "#0a0b0c0d", "#ффф", "#+f+f+f"] {
+      assert!(matches!(invalid.parse::<Rgba>(), Err(Error::InvalidColor(_))), "{invalid}");
+    }
+  }
+
+  #[test]
+  fn colors_are_converted() {
+    let color = Rgba::opaque(0x12, 0x34, 0x56);
+    assert_eq!((color.to_css(), color.to_ass()), ("#123456".into(), "&H00563412".into()));
+
+    let color = color.with_opacity(0x40);
+    assert_eq!((color.to_css(), color.to_ass()), ("#12345640".into(), "&HBF563412".into()));
+  }
+}
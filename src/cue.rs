@@ -0,0 +1,445 @@
+use std::borrow::Cow;
+use std::ops::Deref;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::format::*;
+use crate::time::Timestamp;
+
+/// A piece of text shown on the screen for a while.
+///
+/// Every format is converted to cues, so renderers and exporters are written once.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cue {
+  pub start_millis: u32,
+  pub duration_millis: u32,
+  pub text: String,
+  /// Styled parts of the text, for formats that have them. Their texts add up to [`Cue::text`], without the readings.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub spans: Vec<Span>,
+}
+
+/// A part of a cue shown with its own style, possibly later than the cue itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+  /// Relative to the start of the cue.
+  pub offset_millis: u32,
+  pub text: String,
+  #[serde(default)]
+  pub style: Style,
+  /// The reading of the text, e.g. furigana for kanji.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ruby: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Style {
+  pub bold: bool,
+  pub italic: bool,
+  pub underline: bool,
+}
+
+/// A named part of a video, like the ones listed in its description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chapter {
+  pub start_millis: u32,
+  pub title: String,
+}
+
+/// A word with the timing of its span, or of its cue for formats without spans.
+/// Styled cues may change the style in the middle of a word. Pieces with ruby carry their reading.
+#[derive(Clone)]
+pub(crate) struct Word<'a> {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  pub pieces: Vec<(&'a str, Style, Option<&'a str>)>,
+}
+
+/// Cues ordered by the start time. Only the changes that keep the order are public, like [`Cues::shift`],
+/// [`Cues::clip`] and [`Cues::append`], the rest goes through [`Cues::into_inner`] and back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cues(Vec<Cue>);
+
+impl Cue {
+  pub fn new(start_millis: u32, duration_millis: u32, text: impl Into<String>) -> Self {
+    Self { start_millis, duration_millis, text: text.into(), spans: vec![] }
+  }
+
+  /// A cue made of spans, its text is their concatenation.
+  pub fn with_spans(start_millis: u32, duration_millis: u32, spans: Vec<Span>) -> Self {
+    let text = spans.iter().map(|it| it.text.as_str()).collect();
+    Self { start_millis, duration_millis, text, spans }
+  }
+
+  /// The text with the readings of the spans in parentheses, for formats without ruby support.
+  pub fn fallback_text(&self) -> Cow<'_, str> {
+    if self.spans.iter().all(|it| it.ruby.is_none()) {
+      return Cow::Borrowed(&self.text);
+    }
+    Cow::Owned(self.spans.iter().map(Span::fallback_text).collect())
+  }
+
+  /// Saturates at `u32::MAX`, as the times come from untrusted documents.
+  pub fn end_millis(&self) -> u32 {
+    self.start_millis.saturating_add(self.duration_millis)
+  }
+
+  pub fn start(&self) -> Timestamp {
+    Timestamp::from_millis(self.start_millis)
+  }
+
+  pub fn end(&self) -> Timestamp {
+    Timestamp::from_millis(self.end_millis())
+  }
+}
+
+impl Span {
+  pub fn new(offset_millis: u32, text: impl Into<String>, style: Style) -> Self {
+    Self { offset_millis, text: text.into(), style, ruby: None }
+  }
+
+  pub fn with_ruby(mut self, ruby: impl Into<String>) -> Self {
+    self.ruby = Some(ruby.into());
+    self
+  }
+
+  /// The text with the ruby in parentheses after it, for formats without ruby support.
+  pub fn fallback_text(&self) -> Cow<'_, str> {
+    match &self.ruby {
+      Some(ruby) => Cow::Owned(format!("{}({ruby})", self.text)),
+      None => Cow::Borrowed(&self.text),
+    }
+  }
+}
+
+impl Chapter {
+  pub fn new(start_millis: u32, title: impl Into<String>) -> Self {
+    Self { start_millis, title: title.into() }
+  }
+
+  /// Finds the chapters listed in a video description, one per line, like `1:05 - Coroutines`.
+  ///
+  /// Following YouTube, the list counts only if it starts at `0:00`, has at least 3 chapters in order.
+  pub fn from_description(description: &str) -> Vec<Chapter> {
+    lazy_static::lazy_static! {
+      static ref RE: Regex = Regex::new(r"^\s*\(?(?:(\d+):)?(\d{1,2}):(\d{2})\)?\s*[-–—:|]?\s*(.+?)\s*$").unwrap();
+    }
+
+    // lines with times beyond `u32` millis are skipped
+    let chapter = |line: &str| {
+      let caps = RE.captures(line)?;
+      let number = |i| caps.get(i).map_or(Some(0), |it| it.as_str().parse::<u32>().ok());
+      let secs = number(1)?.checked_mul(3600)?.checked_add(number(2)? * 60 + number(3)?)?;
+      Some(Chapter::new(secs.checked_mul(1000)?, &caps[4]))
+    };
+
+    let chapters: Vec<_> = description.lines().filter_map(chapter).collect();
+    let ordered = chapters.windows(2).all(|it| it[0].start_millis < it[1].start_millis);
+    match chapters.first() {
+      Some(first) if first.start_millis == 0 && chapters.len() >= 3 && ordered => chapters,
+      _ => vec![],
+    }
+  }
+}
+
+impl Cues {
+  /// Parses a document in the given format, see [`Format::detect`].
+  #[cfg_attr(not(any(feature = "srv1", feature = "srv2", feature = "srv3")), allow(unused_variables))]
+  pub fn parse(document: &str, format: Format) -> Result<Self> {
+    match format {
+      #[cfg(feature = "srv1")]
+      Format::SRV1 => Ok(srv1::Transcript::parse(document)?.into()),
+      #[cfg(feature = "srv2")]
+      Format::SRV2 => Ok(srv2::Transcript::parse(document)?.into()),
+      #[cfg(feature = "srv3")]
+      Format::SRV3 => Ok(srv3::Transcript::parse(document)?.into()),
+      _ => Err(Error::UnsupportedFormat(format)),
+    }
+  }
+
+  pub fn into_inner(self) -> Vec<Cue> {
+    self.0
+  }
+
+  /// Keeps the cues the predicate is true for, in the same order.
+  pub fn retain(&mut self, f: impl FnMut(&Cue) -> bool) {
+    self.0.retain(f);
+  }
+
+  /// Like [`Cues::retain`], but the predicate may change the cues, keeping their order.
+  pub(crate) fn retain_mut(&mut self, f: impl FnMut(&mut Cue) -> bool) {
+    self.0.retain_mut(f);
+  }
+
+  /// The changes must keep the order, like moving every cue by the same offset.
+  pub(crate) fn iter_mut(&mut self) -> std::slice::IterMut<'_, Cue> {
+    self.0.iter_mut()
+  }
+
+  /// Splits the cues into words. When a cue overlaps the previous one in time, the words
+  /// already shown by the previous cue are skipped, as rolling captions repeat the last line.
+  pub(crate) fn words(&self) -> Vec<Word<'_>> {
+    let mut words = vec![];
+    let mut prev: Option<(u32, Vec<Word>)> = None;
+
+    for cue in self {
+      let current = cue.words();
+      let skip = match &prev {
+        Some((prev_end, prev)) if cue.start_millis < *prev_end => Word::overlap(prev, &current),
+        _ => 0,
+      };
+
+      words.extend(current[skip..].iter().cloned());
+      prev = Some((cue.end_millis(), current));
+    }
+
+    words
+  }
+}
+
+impl Cue {
+  /// A word of a span lasts until the next one starts.
+  fn words(&self) -> Vec<Word<'_>> {
+    let pieces: Vec<_> = match self.spans.as_slice() {
+      [] => vec![(0, self.text.as_str(), Style::default(), None)],
+      spans => spans.iter().map(|it| (it.offset_millis, it.text.as_str(), it.style, it.ruby.as_deref())).collect(),
+    };
+
+    let mut words: Vec<Word> = vec![];
+    // the previous piece ended in the middle of a word
+    let mut glued = false;
+    for (offset_millis, text, style, ruby) in pieces {
+      let last = text.trim_end().split(char::is_whitespace).count() - 1;
+      for (i, part) in text.split(char::is_whitespace).enumerate() {
+        // the reading belongs to the last word of the base
+        let ruby = ruby.filter(|_| i == last);
+        match words.last_mut() {
+          _ if part.is_empty() => {}
+          Some(word) if i == 0 && glued => word.pieces.push((part, style, ruby)),
+          _ => words.push(Word {
+            start_millis: self.start_millis.saturating_add(offset_millis),
+            end_millis: self.end_millis(),
+            pieces: vec![(part, style, ruby)],
+          }),
+        }
+      }
+      if !text.is_empty() {
+        glued = !text.ends_with(char::is_whitespace);
+      }
+    }
+
+    for i in 1..words.len() {
+      if words[i].start_millis > words[i - 1].start_millis {
+        words[i - 1].end_millis = words[i].start_millis;
+      }
+    }
+    words
+  }
+}
+
+impl Word<'_> {
+  /// The text without the readings.
+  pub fn text(&self) -> Cow<'_, str> {
+    match self.pieces.as_slice() {
+      [(text, _, _)] => Cow::Borrowed(text),
+      pieces => Cow::Owned(pieces.iter().map(|(text, _, _)| *text).collect()),
+    }
+  }
+
+  /// The length of the longest suffix of `prev` that is a prefix of `next`.
+  fn overlap(prev: &[Word], next: &[Word]) -> usize {
+    let same = |a: &[Word], b: &[Word]| a.iter().zip(b).all(|(a, b)| a.text() == b.text());
+    (1..=prev.len().min(next.len())).rev().find(|&len| same(&prev[prev.len() - len..], &next[..len])).unwrap_or(0)
+  }
+}
+
+impl Deref for Cues {
+  type Target = Vec<Cue>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl From<Vec<Cue>> for Cues {
+  fn from(mut cues: Vec<Cue>) -> Self {
+    cues.sort_by_key(|it| it.start_millis);
+    Self(cues)
+  }
+}
+
+impl FromIterator<Cue> for Cues {
+  fn from_iter<T: IntoIterator<Item = Cue>>(iter: T) -> Self {
+    iter.into_iter().collect::<Vec<_>>().into()
+  }
+}
+
+impl IntoIterator for Cues {
+  type Item = Cue;
+  type IntoIter = std::vec::IntoIter<Self::Item>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.into_iter()
+  }
+}
+
+impl<'a> IntoIterator for &'a Cues {
+  type Item = &'a Cue;
+  type IntoIter = std::slice::Iter<'a, Cue>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.iter()
+  }
+}
+
+#[cfg(feature = "srv1")]
+impl From<srv1::TextSegment<'_>> for Cue {
+  fn from(it: srv1::TextSegment<'_>) -> Self {
+    Cue::new(it.start.as_millis(), it.duration.as_millis(), it.value)
+  }
+}
+
+#[cfg(feature = "srv1")]
+impl From<srv1::Transcript<'_>> for Cues {
+  fn from(transcript: srv1::Transcript<'_>) -> Self {
+    transcript.into_iter().map(Cue::from).filter(|it| !it.text.trim().is_empty()).collect()
+  }
+}
+
+#[cfg(feature = "srv2")]
+impl From<srv2::TextSegment<'_>> for Cue {
+  fn from(it: srv2::TextSegment<'_>) -> Self {
+    Cue::new(it.start.as_millis(), it.duration.as_millis(), it.value)
+  }
+}
+
+#[cfg(feature = "srv2")]
+impl From<srv2::Transcript<'_>> for Cues {
+  fn from(transcript: srv2::Transcript<'_>) -> Self {
+    let cue = |it| match it {
+      srv2::Element::Text(it) => Some(Cue::from(it)),
+      srv2::Element::Window(_) => None,
+    };
+    transcript.elements.into_iter().filter_map(cue).filter(|it| !it.text.trim().is_empty()).collect()
+  }
+}
+
+#[cfg(feature = "srv3")]
+impl Cue {
+  /// Pens are looked up in the head, undefined ones are replaced with the default style.
+  pub fn from_srv3(seg: &srv3::TextSegment<'_>, head: &srv3::Head) -> Self {
+    let pen = |pen_id: Option<u32>| head.pen(pen_id).ok();
+    let style = |pen: Option<&srv3::Pen>| {
+      pen.map_or_else(Style::default, |it| Style { bold: it.bold, italic: it.italic, underline: it.underline })
+    };
+
+    let pieces = seg.separated().map(|(separator, text)| match text {
+      srv3::Text::Span(it) => (it.offset.as_millis(), separator, text, pen(it.pen_id.or(seg.pen_id))),
+      srv3::Text::Str(_) => (0, separator, text, pen(seg.pen_id)),
+    });
+    let units = srv3::group_ruby(pieces.collect(), |(_, _, _, pen)| pen.map_or_else(Default::default, |it| it.ruby));
+    let spans = units.into_iter().map(|((offset_millis, separator, text, pen), ruby)| {
+      let span = Span::new(offset_millis, format!("{separator}{}", text.as_ref()), style(pen));
+      match ruby {
+        Some((_, _, ruby, _)) => span.with_ruby(ruby.as_ref()),
+        None => span,
+      }
+    });
+    Cue::with_spans(seg.start.as_millis(), seg.duration.as_millis(), spans.collect())
+  }
+}
+
+#[cfg(feature = "srv3")]
+impl From<srv3::Transcript<'_>> for Cues {
+  fn from(transcript: srv3::Transcript<'_>) -> Self {
+    let cue = |it: &srv3::Element| match it {
+      srv3::Element::Segment(seg) => Some(Cue::from_srv3(seg, &transcript.head)),
+      srv3::Element::Window(_) => None,
+    };
+    transcript.body.elements.iter().filter_map(cue).filter(|it| !it.text.trim().is_empty()).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cues_are_sorted() {
+    let cues: Cues = vec![Cue::new(2000, 500, "world"), Cue::new(1000, 500, "hello")].into();
+    assert_eq!(cues[0].text, "hello");
+    assert_eq!(cues[1].end_millis(), 2500);
+    assert_eq!(Cue::new(u32::MAX - 300, 1000, "late").end_millis(), u32::MAX);
+  }
+
+  #[test]
+  fn chapters_are_found_in_descriptions() {
+    let description = "Sources: https://github.com\n\n0:00 Intro\n1:05 - Coroutines\n1:02:03 Q&A\nThanks!";
+    let chapters = Chapter::from_description(description);
+    assert_eq!(
+      chapters,
+      [Chapter::new(0, "Intro"), Chapter::new(65_000, "Coroutines"), Chapter::new(3_723_000, "Q&A")]
+    );
+
+    assert!(Chapter::from_description("0:30 Intro\n1:05 Coroutines\n2:00 Q&A").is_empty());
+    assert!(Chapter::from_description("0:00 Intro\n1:05 Coroutines").is_empty());
+
+    let description = "0:00 Intro\n1:05 Coroutines\n9999999:00:00 Overflow\n2:00 Q&A";
+    assert_eq!(Chapter::from_description(description).len(), 3);
+  }
+
+  #[cfg(feature = "srv1")]
+  #[test]
+  fn srv1_is_converted() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><transcript>
+      <text start="4.333" dur="2.1">(&quot;Just A Girl&quot; - No Doubt)</text>
+      <text start="8.6" dur="3.9">♪ Take this pink ribbon
+off my eyes ♪</text>
+    </transcript>"#;
+
+    let cues = Cues::parse(xml, Format::SRV1).unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0], Cue::new(4333, 2100, r#"("Just A Girl" - No Doubt)"#));
+    assert_eq!(cues[1].text, "♪ Take this pink ribbon\noff my eyes ♪");
+  }
+
+  #[cfg(feature = "srv3")]
+  #[test]
+  fn srv3_is_converted() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" b="1"/><wp id="0"/><ws id="0"/></head>
+      <body>
+        <w id="1" t="0" wp="0" ws="0"/>
+        <p t="6020" d="8560" w="1"><s ac="0">uh</s><s t="1140" p="1" ac="0"> yeah</s><s t="1500">, right</s></p>
+        <p t="9550" d="5030" w="1" a="1">
+</p>
+      </body>
+    </timedtext>"#;
+
+    let cues = Cues::parse(xml, Format::SRV3).unwrap();
+    assert_eq!(cues.len(), 1);
+    assert_eq!(cues[0].text, "uh yeah, right");
+    let spans: Vec<_> = cues[0].spans.iter().map(|it| (it.offset_millis, it.text.as_str(), it.style.bold)).collect();
+    assert_eq!(spans, [(0, "uh", false), (1140, " yeah", true), (1500, ", right", false)]);
+  }
+
+  #[cfg(feature = "srv3")]
+  #[test]
+  fn srv3_ruby_is_converted() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" rb="1"/><pen id="2" rb="2"/><pen id="3" rb="4"/></head>
+      <body>
+        <p t="0" d="3000"><s p="1">漢字</s><s p="2">(</s><s p="3">かんじ</s><s p="2">)</s><s>を読む</s></p>
+      </body>
+    </timedtext>"#;
+
+    let cues = Cues::parse(xml, Format::SRV3).unwrap();
+    assert_eq!(cues[0].text, "漢字を読む");
+    assert_eq!(cues[0].fallback_text(), "漢字(かんじ)を読む");
+    let spans: Vec<_> = cues[0].spans.iter().map(|it| (it.text.as_str(), it.ruby.as_deref())).collect();
+    assert_eq!(spans, [("漢字", Some("かんじ")), ("を読む", None)]);
+  }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::cue::{Cue, Cues};
+
+/// A difference between two versions of a transcript, see [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CueChange {
+  /// The same text shown at a different time.
+  Retimed {
+    old: Cue,
+    new: Cue,
+  },
+  Inserted(Cue),
+  Removed(Cue),
+  /// A different text shown at an overlapping time.
+  Edited {
+    old: Cue,
+    new: Cue,
+    words: Vec<WordChange>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WordChange {
+  Equal(String),
+  Inserted(String),
+  Removed(String),
+}
+
+/// Word error rate counts, see [`word_error_rate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Wer {
+  pub substitutions: usize,
+  pub deletions: usize,
+  pub insertions: usize,
+  /// The number of words in the reference.
+  pub reference_words: usize,
+}
+
+/// Compares two versions of a transcript cue by cue. Unchanged cues are skipped.
+pub fn diff(old: &Cues, new: &Cues) -> Vec<CueChange> {
+  let text = |cues: &Cues| cues.iter().map(|it| it.text.split_whitespace().collect::<Vec<_>>().join(" ")).collect();
+  let (old_texts, new_texts): (Vec<String>, Vec<String>) = (text(old), text(new));
+
+  let mut changes = vec![];
+  for op in capture_diff_slices(Algorithm::Myers, &old_texts, &new_texts) {
+    let (old_range, new_range) = (op.old_range(), op.new_range());
+    match op {
+      DiffOp::Equal { .. } => {
+        for (old, new) in old[old_range].iter().zip(&new[new_range]) {
+          if (old.start_millis, old.duration_millis) != (new.start_millis, new.duration_millis) {
+            changes.push(CueChange::Retimed { old: old.clone(), new: new.clone() });
+          }
+        }
+      }
+      DiffOp::Delete { .. } => changes.extend(old[old_range].iter().cloned().map(CueChange::Removed)),
+      DiffOp::Insert { .. } => changes.extend(new[new_range].iter().cloned().map(CueChange::Inserted)),
+      DiffOp::Replace { .. } => replace(&old[old_range], &new[new_range], &mut changes),
+    }
+  }
+  changes
+}
+
+/// Pairs the replaced cues shown at the same time as edits, the rest are removed or inserted.
+fn replace(old: &[Cue], new: &[Cue], changes: &mut Vec<CueChange>) {
+  let (mut old, mut new) = (old.iter().peekable(), new.iter().peekable());
+  loop {
+    let change = match (old.peek(), new.peek()) {
+      (Some(a), Some(b)) if a.start_millis < b.end_millis() && b.start_millis < a.end_millis() => {
+        let words = diff_words(&a.text, &b.text);
+        CueChange::Edited { old: old.next().unwrap().clone(), new: new.next().unwrap().clone(), words }
+      }
+      (Some(a), Some(b)) if a.start_millis <= b.start_millis => CueChange::Removed(old.next().unwrap().clone()),
+      (Some(_), None) => CueChange::Removed(old.next().unwrap().clone()),
+      (_, Some(_)) => CueChange::Inserted(new.next().unwrap().clone()),
+      (None, None) => break,
+    };
+    changes.push(change);
+  }
+}
+
+/// Compares two texts word by word, ignoring the whitespaces.
+pub fn diff_words(old: &str, new: &str) -> Vec<WordChange> {
+  let old: Vec<_> = old.split_whitespace().collect();
+  let new: Vec<_> = new.split_whitespace().collect();
+
+  let mut changes = vec![];
+  for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+    let removed = old[op.old_range()].iter().map(|it| WordChange::Removed(it.to_string()));
+    let inserted = new[op.new_range()].iter().map(|it| WordChange::Inserted(it.to_string()));
+    match op {
+      DiffOp::Equal { .. } => changes.extend(new[op.new_range()].iter().map(|it| WordChange::Equal(it.to_string()))),
+      _ => changes.extend(removed.chain(inserted)),
+    }
+  }
+  changes
+}
+
+/// Counts the word edits turning the reference, e.g. manual captions, into the hypothesis, e.g. generated ones.
+///
+/// Words are compared case-insensitively, ignoring the punctuation around them. Annotations count as words,
+/// so it makes sense to strip them beforehand with [`Cues::strip_annotations`].
+pub fn word_error_rate(reference: &Cues, hypothesis: &Cues) -> Wer {
+  let words = |cues: &Cues| -> Vec<String> {
+    let normalize = |word: &str| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+    cues.words().iter().map(|it| normalize(&it.text())).filter(|it| !it.is_empty()).collect()
+  };
+  let (reference, hypothesis) = (words(reference), words(hypothesis));
+
+  // Levenshtein distance over words, keeping the kind of each edit
+  let mut prev: Vec<Wer> = (0..=hypothesis.len()).map(|i| Wer { insertions: i, ..Default::default() }).collect();
+  for (i, expected) in reference.iter().enumerate() {
+    let mut row = vec![Wer { deletions: i + 1, ..Default::default() }];
+    for (j, actual) in hypothesis.iter().enumerate() {
+      let substitution =
+        if expected == actual { prev[j] } else { Wer { substitutions: prev[j].substitutions + 1, ..prev[j] } };
+      let deletion = Wer { deletions: prev[j + 1].deletions + 1, ..prev[j + 1] };
+      let insertion = Wer { insertions: row[j].insertions + 1, ..row[j] };
+      row.push([substitution, deletion, insertion].into_iter().min_by_key(Wer::errors).unwrap());
+    }
+    prev = row;
+  }
+
+  Wer { reference_words: reference.len(), ..prev[hypothesis.len()] }
+}
+
+impl Wer {
+  pub fn errors(&self) -> usize {
+    self.substitutions + self.deletions + self.insertions
+  }
+
+  /// `errors / reference_words`, may exceed 1 when the hypothesis has many extra words.
+  pub fn rate(&self) -> f64 {
+    match self.reference_words {
+      0 if self.errors() == 0 => 0.0,
+      0 => 1.0,
+      words => self.errors() as f64 / words as f64,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cues_are_compared() {
+    let old: Cues = vec![
+      Cue::new(0, 1000, "Hello everyone,"),
+      Cue::new(1000, 1000, "welcome to the\nstream."),
+      Cue::new(2000, 1000, "Let's start."),
+    ]
+    .into();
+    let new: Cues = vec![
+      Cue::new(0, 1200, "Hello everyone,"),
+      Cue::new(1200, 800, "welcome to our stream!"),
+      Cue::new(2000, 1000, "Let's start."),
+      Cue::new(3000, 1000, "First, the basics."),
+    ]
+    .into();
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes.len(), 3);
+    assert!(matches!(&changes[0], CueChange::Retimed { new, .. } if new.duration_millis == 1200));
+    let CueChange::Edited { words, .. } = &changes[1] else { panic!("{:?}", changes[1]) };
+    let removed: Vec<_> = words.iter().filter(|it| matches!(it, WordChange::Removed(_))).collect();
+    assert_eq!(removed, [&WordChange::Removed("the".into()), &WordChange::Removed("stream.".into())]);
+    assert!(matches!(&changes[2], CueChange::Inserted(it) if it.text == "First, the basics."));
+  }
+
+  #[test]
+  fn wer() {
+    let reference: Cues = vec![Cue::new(0, 1000, "The cat sat"), Cue::new(1000, 1000, "on the mat.")].into();
+    let hypothesis: Cues = vec![Cue::new(0, 2000, "the cat sad on mat today")].into();
+
+    let wer = word_error_rate(&reference, &hypothesis);
+    assert_eq!((wer.errors(), wer.reference_words), (3, 6));
+    assert_eq!(wer.rate(), 0.5);
+
+    let hypothesis: Cues = vec![Cue::new(0, 2000, "the cat sad on mat")].into();
+    let wer = word_error_rate(&reference, &hypothesis);
+    assert_eq!((wer.substitutions, wer.deletions, wer.insertions), (1, 1, 0));
+    assert_eq!(word_error_rate(&reference, &reference).rate(), 0.0);
+  }
+}
@@ -6,9 +6,20 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// The Errors that may occur while scraping captions.
 #[derive(Debug, Error)]
 pub enum Error {
+  /// Neither a video id, nor a link to a video.
+  #[error("{0:?} is not a YouTube video id")]
+  InvalidVideoId(String),
+
   #[error("the video is no longer available")]
   VideoUnavailable,
 
+  /// Neither a playlist id, nor a link to a playlist or a channel.
+  #[error("{0:?} is not a YouTube playlist or channel")]
+  InvalidPlaylist(String),
+
+  #[error("the playlist or the channel is not available")]
+  PlaylistUnavailable,
+
   /// YouTube is receiving too many requests from this IP and now requires solving a captcha to continue.
   /// One of the following things can be done to work around this:
   /// - Manually solve the captcha in a browser and export the cookie
@@ -31,6 +42,22 @@ pub enum Error {
   // #[error("No transcripts are available for this video")]
   // NoTranscriptAvailable,
 
+  /// None of the caption tracks satisfies the requested language.
+  #[error("No transcripts were found for the requested language")]
+  NoTranscriptFound,
+
+  /// The live stream or the premiere has not started yet.
+  #[error("The live stream has not started yet")]
+  LiveStreamUpcoming { scheduled_start: Option<std::time::SystemTime> },
+
+  /// Captions of a live stream can be fetched only after the stream is over.
+  #[error("The live stream is still in progress")]
+  LiveStreamInProgress,
+
+  /// The live stream is over, but its automatically generated captions are still being processed.
+  #[error("Captions of the finished live stream are not available yet")]
+  CaptionsNotYetAvailable,
+
   /// The requested file is not translatable.
   #[error("The requested file is not translatable")]
   NotTranslatable,
@@ -47,6 +74,26 @@ pub enum Error {
   #[error("Failed to automatically give consent to saving cookies")]
   FailedToCreateConsentCookie,
 
+  /// The format is either not supported yet, or its cargo feature is disabled.
+  #[error("Parsing {0:?} is not supported")]
+  UnsupportedFormat(crate::format::Format),
+
+  /// A segment or a span refers to a pen or a window missing in the srv3 head.
+  #[cfg(feature = "srv3")]
+  #[error("{0} is not defined in the head")]
+  UndefinedInHead(crate::format::srv3::HeadRef),
+
+  /// Neither a clock, like `01:02:03.456`, nor an offset, like `4.5s`.
+  #[error("{0:?} is not a timestamp")]
+  InvalidTimestamp(String),
+
+  /// Not a `#RRGGBB` color.
+  #[error("{0:?} is not a color")]
+  InvalidColor(String),
+
+  #[error("Invalid search pattern: {0}")]
+  InvalidPattern(#[from] regex::Error),
+
   #[error("Request to YouTube failed: {0}")]
   NetworkError(#[from] reqwest::Error),
 
@@ -56,4 +103,8 @@ pub enum Error {
   #[cfg(feature = "quick-xml")]
   #[error("Invalid XML: {0}")]
   InvalidXml(#[from] quick_xml::de::DeError),
+
+  #[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+  #[error("Invalid document at {0}")]
+  InvalidDocument(#[from] crate::format::ParseError),
 }
@@ -0,0 +1,179 @@
+use std::fmt::Write;
+
+use crate::cue::{Cues, Style, Word};
+use crate::video_id::VideoId;
+
+use super::text::{group, short_clock, TextOptions};
+
+/// Renders a Markdown document: chapters become headings, each paragraph starts with a link to its moment in the video.
+pub fn to_markdown(cues: &Cues, video_id: &VideoId, title: Option<&str>, options: &TextOptions) -> String {
+  let mut out = String::new();
+  if let Some(title) = title {
+    let _ = writeln!(out, "# {}\n", escape_markdown(title));
+  }
+
+  let mut chapter = None;
+  for (paragraph, words) in group(cues, options) {
+    if paragraph.chapter != chapter {
+      chapter = paragraph.chapter;
+      if let Some(it) = chapter.and_then(|i| options.chapters.get(i)) {
+        let _ = writeln!(out, "## {}\n", escape_markdown(&it.title));
+      }
+    }
+    let (clock, link) = (short_clock(paragraph.start_millis), deep_link(video_id, paragraph.start_millis));
+    let _ = writeln!(out, "[{clock}]({link}) {}\n", styled(&words, escape_markdown, markdown_tags, markdown_ruby));
+  }
+
+  out
+}
+
+/// Same as [`to_markdown`], but renders an HTML fragment.
+pub fn to_html(cues: &Cues, video_id: &VideoId, title: Option<&str>, options: &TextOptions) -> String {
+  let mut out = String::new();
+  if let Some(title) = title {
+    let _ = writeln!(out, "<h1>{}</h1>", escape_html(title));
+  }
+
+  let mut chapter = None;
+  for (paragraph, words) in group(cues, options) {
+    if paragraph.chapter != chapter {
+      chapter = paragraph.chapter;
+      if let Some(it) = chapter.and_then(|i| options.chapters.get(i)) {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(&it.title));
+      }
+    }
+    let (clock, link) = (short_clock(paragraph.start_millis), deep_link(video_id, paragraph.start_millis));
+    let _ =
+      writeln!(out, r#"<p><a href="{link}">{clock}</a> {}</p>"#, styled(&words, escape_html, html_tags, html_ruby));
+  }
+
+  out
+}
+
+fn deep_link(video_id: &VideoId, millis: u32) -> String {
+  format!("https://youtu.be/{video_id}?t={}", millis / 1000)
+}
+
+/// Joins the words, wrapping runs of the same style into the tags. Spaces are kept outside of the tags.
+fn styled(
+  words: &[Word],
+  escape: fn(&str) -> String,
+  tags: fn(Style) -> (String, String),
+  ruby: fn(&str, &str) -> String,
+) -> String {
+  let mut runs: Vec<(String, Style)> = vec![];
+  for (i, word) in words.iter().enumerate() {
+    for (j, (text, style, reading)) in word.pieces.iter().enumerate() {
+      let separator = if i > 0 && j == 0 { " " } else { "" };
+      match runs.last_mut() {
+        Some((run, last)) if last == style => run.push_str(separator),
+        last => {
+          if let Some((run, _)) = last {
+            run.push_str(separator);
+          }
+          runs.push((String::new(), *style));
+        }
+      }
+      let text = match reading {
+        Some(reading) => ruby(&escape(text), &escape(reading)),
+        None => escape(text),
+      };
+      runs.last_mut().unwrap().0.push_str(&text);
+    }
+  }
+
+  let mut out = String::new();
+  for (text, style) in runs {
+    let (open, close) = tags(style);
+    let trimmed = text.trim_end();
+    let _ = write!(out, "{open}{trimmed}{close}{}", &text[trimmed.len()..]);
+  }
+  out
+}
+
+fn markdown_tags(style: Style) -> (String, String) {
+  let tags = [(style.underline, "<u>", "</u>"), (style.bold, "**", "**"), (style.italic, "*", "*")];
+  let open = tags.iter().filter(|it| it.0).map(|it| it.1).collect();
+  let close = tags.iter().rev().filter(|it| it.0).map(|it| it.2).collect();
+  (open, close)
+}
+
+fn html_tags(style: Style) -> (String, String) {
+  let tags = [(style.underline, "<u>", "</u>"), (style.bold, "<b>", "</b>"), (style.italic, "<i>", "</i>")];
+  let open = tags.iter().filter(|it| it.0).map(|it| it.1).collect();
+  let close = tags.iter().rev().filter(|it| it.0).map(|it| it.2).collect();
+  (open, close)
+}
+
+fn markdown_ruby(base: &str, reading: &str) -> String {
+  format!("{base}({reading})")
+}
+
+fn html_ruby(base: &str, reading: &str) -> String {
+  format!("<ruby>{base}<rt>{reading}</rt></ruby>")
+}
+
+fn escape_markdown(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  out
+}
+
+fn escape_html(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::{Chapter, Cue, Span};
+
+  fn cues() -> Cues {
+    let bold = Style { bold: true, ..Default::default() };
+    let italic = Style { italic: true, ..Default::default() };
+    vec![
+      Cue::new(0, 2000, "Welcome to the *stream*."),
+      Cue::with_spans(
+        65_000,
+        3000,
+        vec![
+          Span::new(0, "Today: ", Style::default()),
+          Span::new(500, "coroutines", bold).with_ruby("コルーチン"),
+          Span::new(1500, "!", bold),
+          Span::new(2000, " <3", italic),
+        ],
+      ),
+    ]
+    .into()
+  }
+
+  fn options() -> TextOptions {
+    TextOptions { chapters: vec![Chapter::new(0, "Intro"), Chapter::new(60_000, "Main part")], ..Default::default() }
+  }
+
+  #[test]
+  fn markdown() {
+    let video_id = "JRMOIE_wAFk".parse().unwrap();
+    let expected = "# Kotlin \\[live\\]\n\n\
+                    ## Intro\n\n\
+                    [00:00](https://youtu.be/JRMOIE_wAFk?t=0) Welcome to the \\*stream\\*.\n\n\
+                    ## Main part\n\n\
+                    [01:05](https://youtu.be/JRMOIE_wAFk?t=65) Today: **coroutines(コルーチン)!** *\\<3*\n\n";
+    assert_eq!(to_markdown(&cues(), &video_id, Some("Kotlin [live]"), &options()), expected);
+  }
+
+  #[test]
+  fn html() {
+    let video_id = "JRMOIE_wAFk".parse().unwrap();
+    let expected = "<h2>Intro</h2>\n\
+                    <p><a href=\"https://youtu.be/JRMOIE_wAFk?t=0\">00:00</a> Welcome to the *stream*.</p>\n\
+                    <h2>Main part</h2>\n\
+                    <p><a href=\"https://youtu.be/JRMOIE_wAFk?t=65\">01:05</a> Today: <b><ruby>coroutines<rt>コルーチン</rt></ruby>!</b> <i>&lt;3</i></p>\n";
+    assert_eq!(to_html(&cues(), &video_id, None, &options()), expected);
+  }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use crate::align::Pair;
+use crate::cue::{Cue, Cues};
+use crate::time::Timestamp;
+
+use super::{escape_ass, to_srt, ASS_EVENTS, ASS_HEADER};
+
+/// The secondary text is shown at the top, smaller and in yellow.
+const ASS_DUAL_STYLES: &str = "\
+Style: Primary,Roboto,60,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,2,40,40,40,1
+Style: Secondary,Roboto,48,&H0000FFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,8,40,40,40,1
+";
+
+/// SubRip with the primary text on the first line and the secondary text below it.
+pub fn to_dual_srt(pairs: &[Pair]) -> String {
+  let cue = |it: &Pair| {
+    let text: Vec<_> = [it.primary.as_str(), it.secondary.as_str()].into_iter().filter(|it| !it.is_empty()).collect();
+    Cue::new(it.start_millis, it.end_millis.saturating_sub(it.start_millis), text.join("\n"))
+  };
+  to_srt(&pairs.iter().map(cue).collect::<Cues>())
+}
+
+/// Advanced SubStation Alpha with a style for each track.
+pub fn to_dual_ass(pairs: &[Pair]) -> String {
+  let mut out = format!("{ASS_HEADER}{ASS_DUAL_STYLES}{ASS_EVENTS}");
+  for it in pairs {
+    let (start, end) =
+      (Timestamp::from_millis(it.start_millis).to_ass(), Timestamp::from_millis(it.end_millis).to_ass());
+    for (style, text) in [("Primary", &it.primary), ("Secondary", &it.secondary)] {
+      if !text.is_empty() {
+        let _ = writeln!(out, "Dialogue: 0,{start},{end},{style},,0,0,0,,{}", escape_ass(text));
+      }
+    }
+  }
+  out
+}
+
+/// The pairs side by side, as a JSON array of [`Pair`].
+pub fn to_dual_json(pairs: &[Pair]) -> String {
+  serde_json::to_string_pretty(pairs).expect("pairs are always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pairs() -> Vec<Pair> {
+    let pair = |start_millis, end_millis, primary: &str, secondary: &str| Pair {
+      start_millis,
+      end_millis,
+      primary: primary.into(),
+      secondary: secondary.into(),
+    };
+    vec![pair(0, 4000, "Hello everyone!", "Всем привет!"), pair(9000, 10000, "(laughs)", "")]
+  }
+
+  #[test]
+  fn dual_srt() {
+    let expected = "1\n00:00:00,000 --> 00:00:04,000\nHello everyone!\nВсем привет!\n\n\
+                    2\n00:00:09,000 --> 00:00:10,000\n(laughs)\n\n";
+    assert_eq!(to_dual_srt(&pairs()), expected);
+  }
+
+  #[test]
+  fn dual_ass() {
+    let ass = to_dual_ass(&pairs());
+    assert!(ass.contains("Style: Secondary,"));
+    assert!(ass.ends_with(
+      "Dialogue: 0,0:00:00.00,0:00:04.00,Primary,,0,0,0,,Hello everyone!\n\
+       Dialogue: 0,0:00:00.00,0:00:04.00,Secondary,,0,0,0,,Всем привет!\n\
+       Dialogue: 0,0:00:09.00,0:00:10.00,Primary,,0,0,0,,(laughs)\n"
+    ));
+  }
+}
@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use crate::cue::{Cue, Cues};
+use crate::time::Duration;
+
+use super::{escape_ass, escape_vtt, vtt_span, ASS_DEFAULT_STYLE, ASS_EVENTS, ASS_HEADER};
+
+/// Advanced SubStation Alpha, every span is highlighted by a `\k` tag when it is said.
+pub fn to_karaoke_ass(cues: &Cues) -> String {
+  let mut out = format!("{ASS_HEADER}{ASS_DEFAULT_STYLE}{ASS_EVENTS}");
+  for cue in cues {
+    let (start, end) = (cue.start().to_ass(), cue.end().to_ass());
+    let _ = writeln!(out, "Dialogue: 0,{start},{end},Default,,0,0,0,,{}", ass_karaoke(cue));
+  }
+  out
+}
+
+/// Web Video Text Tracks, every span said after the cue start is preceded by a timestamp tag.
+pub fn to_karaoke_vtt(cues: &Cues) -> String {
+  let mut out = String::from("WEBVTT\n\n");
+  for cue in cues {
+    let (start, end) = (cue.start().to_vtt(), cue.end().to_vtt());
+    let _ = write!(out, "{start} --> {end}\n{}\n\n", vtt_karaoke(cue));
+  }
+  out
+}
+
+/// `\k` durations are in centiseconds, each counted from the end of the previous syllable.
+fn ass_karaoke(cue: &Cue) -> String {
+  let duration = shown_millis(cue);
+  if cue.spans.is_empty() {
+    return format!("{{\\k{}}}{}", duration / 10, escape_ass(cue.text.trim()));
+  }
+
+  let offsets: Vec<_> = cue.spans.iter().map(|it| it.offset_millis.min(duration) / 10).collect();
+  let mut out = String::new();
+  if offsets[0] > 0 {
+    // an empty syllable for the pause before the first span
+    let _ = write!(out, "{{\\k{}}}", offsets[0]);
+  }
+  for (i, span) in cue.spans.iter().enumerate() {
+    let end = offsets.get(i + 1).copied().unwrap_or(duration / 10);
+    let _ = write!(out, "{{\\k{}}}{}", end.saturating_sub(offsets[i]), escape_ass(&span.fallback_text()));
+  }
+  out
+}
+
+fn vtt_karaoke(cue: &Cue) -> String {
+  if cue.spans.is_empty() {
+    return escape_vtt(cue.text.trim());
+  }
+
+  let mut out = String::new();
+  for span in &cue.spans {
+    if span.offset_millis > 0 && span.offset_millis < shown_millis(cue) {
+      let _ = write!(out, "<{}>", (cue.start() + Duration::from_millis(span.offset_millis)).to_vtt());
+    }
+    out.push_str(&vtt_span(span));
+  }
+  out.trim().to_owned()
+}
+
+/// The duration up to [`Cue::end`], which saturates for cues near `u32::MAX`.
+fn shown_millis(cue: &Cue) -> u32 {
+  cue.end_millis() - cue.start_millis
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::Span;
+
+  fn cues() -> Cues {
+    let span = |offset_millis, text| Span::new(offset_millis, text, Default::default());
+    let spans = vec![span(200, "Take"), span(700, " this"), span(1500, " <pink>")];
+    vec![Cue::with_spans(61_000, 2000, spans), Cue::new(64_000, 1000, "ribbon")].into()
+  }
+
+  #[test]
+  fn ass() {
+    let ass = to_karaoke_ass(&cues());
+    assert!(ass.contains(",Default,,0,0,0,,{\\k20}{\\k50}Take{\\k80} this{\\k50} <pink>\n"));
+    assert!(ass.ends_with(",Default,,0,0,0,,{\\k100}ribbon\n"));
+  }
+
+  #[test]
+  fn vtt() {
+    let expected = "WEBVTT\n\n\
+                    00:01:01.000 --> 00:01:03.000\n<00:01:01.200>Take<00:01:01.700> this<00:01:02.500> &lt;pink&gt;\n\n\
+                    00:01:04.000 --> 00:01:05.000\nribbon\n\n";
+    assert_eq!(to_karaoke_vtt(&cues()), expected);
+  }
+
+  #[test]
+  fn late_cues_end_at_the_last_timestamp() {
+    let span = |offset_millis, text| Span::new(offset_millis, text, Default::default());
+    let spans = vec![span(0, "Take"), span(300, " this"), span(1500, " ribbon")];
+    let cues: Cues = vec![Cue::with_spans(u32::MAX - 500, 2000, spans)].into();
+
+    let ass = to_karaoke_ass(&cues);
+    assert!(
+      ass.ends_with("0,1193:02:46.79,1193:02:47.29,Default,,0,0,0,,{\\k30}Take{\\k20} this{\\k0} ribbon\n"),
+      "{ass}"
+    );
+    let vtt = to_karaoke_vtt(&cues);
+    assert!(vtt.ends_with("1193:02:46.795 --> 1193:02:47.295\nTake<1193:02:47.095> this ribbon\n\n"), "{vtt}");
+  }
+}
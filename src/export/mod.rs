@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use crate::cue::{Cue, Cues, Span};
+
+mod document;
+mod dual;
+mod karaoke;
+mod text;
+
+pub use document::{to_html, to_markdown};
+pub use dual::{to_dual_ass, to_dual_json, to_dual_srt};
+pub use karaoke::{to_karaoke_ass, to_karaoke_vtt};
+pub use text::{paragraphs, to_text, Paragraph, ParagraphBreak, TextOptions};
+
+/// SubRip, `HH:MM:SS,mmm` timestamps. Readings are shown in parentheses after their base.
+pub fn to_srt(cues: &Cues) -> String {
+  let mut out = String::new();
+  for (i, cue) in cues.iter().enumerate() {
+    let (start, end) = (cue.start().to_srt(), cue.end().to_srt());
+    let _ = write!(out, "{}\n{start} --> {end}\n{}\n\n", i + 1, cue.fallback_text().trim());
+  }
+  out
+}
+
+/// Web Video Text Tracks, `HH:MM:SS.mmm` timestamps.
+pub fn to_vtt(cues: &Cues) -> String {
+  let mut out = String::from("WEBVTT\n\n");
+  for cue in cues {
+    let (start, end) = (cue.start().to_vtt(), cue.end().to_vtt());
+    let _ = write!(out, "{start} --> {end}\n{}\n\n", vtt_text(cue));
+  }
+  out
+}
+
+/// Readings of the spans become ruby tags, otherwise the text is shown as is.
+fn vtt_text(cue: &Cue) -> String {
+  if cue.spans.iter().all(|it| it.ruby.is_none()) {
+    return escape_vtt(cue.text.trim());
+  }
+  cue.spans.iter().map(vtt_span).collect::<String>().trim().to_owned()
+}
+
+fn vtt_span(span: &Span) -> String {
+  match &span.ruby {
+    Some(ruby) => format!("<ruby>{}<rt>{}</rt></ruby>", escape_vtt(&span.text), escape_vtt(ruby)),
+    None => escape_vtt(&span.text),
+  }
+}
+
+/// Advanced SubStation Alpha, `H:MM:SS.cc` timestamps. Readings are shown in parentheses after their base.
+pub fn to_ass(cues: &Cues) -> String {
+  let mut out = format!("{ASS_HEADER}{ASS_DEFAULT_STYLE}{ASS_EVENTS}");
+  for cue in cues {
+    let (start, end) = (cue.start().to_ass(), cue.end().to_ass());
+    let _ = writeln!(out, "Dialogue: 0,{start},{end},Default,,0,0,0,,{}", escape_ass(cue.fallback_text().trim()));
+  }
+  out
+}
+
+/// One cue per line, without timing.
+pub fn to_txt(cues: &Cues) -> String {
+  let mut out = String::new();
+  for cue in cues {
+    out.push_str(&cue.text.trim().replace('\n', " "));
+    out.push('\n');
+  }
+  out
+}
+
+const ASS_HEADER: &str = "[Script Info]
+ScriptType: v4.00+
+PlayResX: 1920
+PlayResY: 1080
+WrapStyle: 0
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+";
+
+const ASS_DEFAULT_STYLE: &str =
+  "Style: Default,Roboto,60,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,2,40,40,40,1\n";
+
+const ASS_EVENTS: &str = "
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+";
+
+pub(crate) fn escape_vtt(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub(crate) fn escape_ass(text: &str) -> String {
+  text.replace('{', "\\{").replace('}', "\\}").replace('\n', "\\N")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cues() -> Cues {
+    vec![
+      Cue::new(4333, 2100, "(\"Just A Girl\" - No Doubt)"),
+      Cue::new(3_725_005, 3900, "♪ Take <this>\noff my eyes ♪"),
+    ]
+    .into()
+  }
+
+  #[test]
+  fn srt() {
+    let expected = "1\n00:00:04,333 --> 00:00:06,433\n(\"Just A Girl\" - No Doubt)\n\n\
+                    2\n01:02:05,005 --> 01:02:08,905\n♪ Take <this>\noff my eyes ♪\n\n";
+    assert_eq!(to_srt(&cues()), expected);
+  }
+
+  #[test]
+  fn vtt() {
+    let expected = "WEBVTT\n\n00:00:04.333 --> 00:00:06.433\n(\"Just A Girl\" - No Doubt)\n\n\
+                    01:02:05.005 --> 01:02:08.905\n♪ Take &lt;this&gt;\noff my eyes ♪\n\n";
+    assert_eq!(to_vtt(&cues()), expected);
+  }
+
+  #[test]
+  fn vtt_ruby() {
+    let spans =
+      vec![Span::new(0, "漢字", Default::default()).with_ruby("かんじ"), Span::new(0, "を読む", Default::default())];
+    let cues: Cues = vec![Cue::with_spans(0, 1000, spans)].into();
+    assert!(to_vtt(&cues).ends_with("\n<ruby>漢字<rt>かんじ</rt></ruby>を読む\n\n"));
+    assert!(to_srt(&cues).ends_with("\n漢字(かんじ)を読む\n\n"));
+    assert_eq!(to_txt(&cues), "漢字を読む\n");
+  }
+
+  #[test]
+  fn late_cues_saturate() {
+    let spans = vec![Span::new(0, "Take", Default::default()), Span::new(u32::MAX, " this", Default::default())];
+    let cues: Cues = vec![Cue::with_spans(u32::MAX - 500, 2000, spans)].into();
+    let end = "1193:02:47,295";
+
+    assert!(to_srt(&cues).contains(&format!("--> {end}\n")));
+    assert!(to_ass(&cues).contains(",1193:02:47.29,Default,"));
+    let paragraphs = paragraphs(&cues, &TextOptions::default());
+    assert_eq!((paragraphs[0].end_millis, paragraphs[0].text.as_str()), (u32::MAX, "Take this"));
+
+    let pairs = crate::align::align(&cues, &cues);
+    assert!(to_dual_srt(&pairs).contains(&format!("--> {end}\n")));
+  }
+
+  #[test]
+  fn ass() {
+    let ass = to_ass(&cues());
+    assert!(ass.starts_with("[Script Info]"));
+    assert!(ass.ends_with("Dialogue: 0,1:02:05.00,1:02:08.90,Default,,0,0,0,,♪ Take <this>\\Noff my eyes ♪\n"));
+  }
+
+  #[test]
+  fn txt() {
+    assert_eq!(to_txt(&cues()), "(\"Just A Girl\" - No Doubt)\n♪ Take <this> off my eyes ♪\n");
+  }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cue::{Chapter, Cues, Word};
+
+/// Decides where a new paragraph begins. Chapters, if known, always start a new paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParagraphBreak {
+  /// After a silence at least that long, in milliseconds.
+  Pause(u32),
+  /// After the given number of sentences.
+  Sentences(usize),
+  /// Only at the beginning of a chapter.
+  Chapter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextOptions {
+  pub paragraph_break: ParagraphBreak,
+  /// Chapters ordered by the start time, e.g. taken from the video description.
+  pub chapters: Vec<Chapter>,
+  /// Prefix each paragraph with `[mm:ss]`.
+  pub timestamps: bool,
+}
+
+/// Text of consecutive cues, joined into lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Paragraph {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  /// Index in [`TextOptions::chapters`].
+  pub chapter: Option<usize>,
+  pub text: String,
+}
+
+impl Default for TextOptions {
+  fn default() -> Self {
+    Self { paragraph_break: ParagraphBreak::Pause(2000), chapters: vec![], timestamps: false }
+  }
+}
+
+/// Renders a readable transcript: paragraphs separated by an empty line, chapter titles on their own lines.
+pub fn to_text(cues: &Cues, options: &TextOptions) -> String {
+  let mut out = String::new();
+  let mut chapter = None;
+
+  for paragraph in paragraphs(cues, options) {
+    if !out.is_empty() {
+      out.push('\n');
+    }
+    if paragraph.chapter != chapter {
+      chapter = paragraph.chapter;
+      if let Some(it) = chapter.and_then(|i| options.chapters.get(i)) {
+        let _ = writeln!(out, "{}\n", it.title);
+      }
+    }
+    if options.timestamps {
+      let _ = write!(out, "[{}] ", short_clock(paragraph.start_millis));
+    }
+    out.push_str(&paragraph.text);
+    out.push('\n');
+  }
+
+  out
+}
+
+/// Joins the cues into paragraphs, removing line breaks inside sentences and the text repeated by rolling captions.
+pub fn paragraphs(cues: &Cues, options: &TextOptions) -> Vec<Paragraph> {
+  group(cues, options).into_iter().map(|(paragraph, _)| paragraph).collect()
+}
+
+/// Same as [`paragraphs`], keeping the words of each paragraph.
+pub(crate) fn group<'a>(cues: &'a Cues, options: &TextOptions) -> Vec<(Paragraph, Vec<Word<'a>>)> {
+  let chapter_of = |millis: u32| options.chapters.iter().rposition(|it| it.start_millis <= millis);
+
+  let mut paragraphs: Vec<(Paragraph, Vec<Word>)> = vec![];
+  let mut sentences = 0;
+
+  for word in cues.words() {
+    let chapter = chapter_of(word.start_millis);
+    let starts_paragraph = match paragraphs.last() {
+      None => true,
+      Some((last, _)) if last.chapter != chapter => true,
+      Some((last, _)) => match options.paragraph_break {
+        ParagraphBreak::Pause(millis) => word.start_millis.saturating_sub(last.end_millis) >= millis,
+        ParagraphBreak::Sentences(count) => sentences >= count.max(1),
+        ParagraphBreak::Chapter => false,
+      },
+    };
+
+    if starts_paragraph {
+      sentences = 0;
+      let paragraph =
+        Paragraph { start_millis: word.start_millis, end_millis: word.end_millis, chapter, text: String::new() };
+      paragraphs.push((paragraph, vec![]));
+    }
+
+    let (paragraph, words) = paragraphs.last_mut().unwrap();
+    let text = word.text();
+    if !paragraph.text.is_empty() {
+      paragraph.text.push(' ');
+    }
+    paragraph.text.push_str(&text);
+    paragraph.end_millis = paragraph.end_millis.max(word.end_millis);
+    if ends_sentence(&text) {
+      sentences += 1;
+    }
+    words.push(word);
+  }
+
+  paragraphs
+}
+
+fn ends_sentence(word: &str) -> bool {
+  word.trim_end_matches(['"', '\'', ')', '»', '”', '’']).ends_with(['.', '!', '?', '…', '。', '！', '？'])
+}
+
+/// `mm:ss`, or `h:mm:ss` for long videos.
+pub(crate) fn short_clock(millis: u32) -> String {
+  let secs = millis / 1000;
+  match secs / 3600 {
+    0 => format!("{:02}:{:02}", secs / 60, secs % 60),
+    hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::Cue;
+
+  fn cues() -> Cues {
+    vec![
+      Cue::new(0, 2000, "Hello everyone,\nwelcome to"),
+      Cue::new(2000, 2000, "the stream. Today we talk"),
+      Cue::new(4000, 1500, "about coroutines."),
+      Cue::new(9000, 2000, "Let's start. First,"),
+      Cue::new(11000, 2000, "the basics."),
+    ]
+    .into()
+  }
+
+  #[test]
+  fn paragraphs_are_broken_by_pauses() {
+    let options = TextOptions { timestamps: true, ..Default::default() };
+    let expected = "[00:00] Hello everyone, welcome to the stream. Today we talk about coroutines.\n\n\
+                    [00:09] Let's start. First, the basics.\n";
+    assert_eq!(to_text(&cues(), &options), expected);
+  }
+
+  #[test]
+  fn paragraphs_are_broken_by_sentences() {
+    let options = TextOptions { paragraph_break: ParagraphBreak::Sentences(2), ..Default::default() };
+    let paragraphs = paragraphs(&cues(), &options);
+    let texts: Vec<_> = paragraphs.iter().map(|it| it.text.as_str()).collect();
+    assert_eq!(
+      texts,
+      ["Hello everyone, welcome to the stream. Today we talk about coroutines.", "Let's start. First, the basics."]
+    );
+    assert_eq!((paragraphs[1].start_millis, paragraphs[1].end_millis), (9000, 13000));
+  }
+
+  #[test]
+  fn paragraphs_are_broken_by_chapters() {
+    let options = TextOptions {
+      paragraph_break: ParagraphBreak::Chapter,
+      chapters: vec![Chapter::new(0, "Intro"), Chapter::new(2000, "Coroutines")],
+      timestamps: false,
+    };
+    let expected = "Intro\n\nHello everyone, welcome to\n\nCoroutines\n\n\
+                    the stream. Today we talk about coroutines. Let's start. First, the basics.\n";
+    assert_eq!(to_text(&cues(), &options), expected);
+  }
+
+  #[test]
+  fn rolling_text_is_not_repeated() {
+    let cues: Cues = vec![
+      Cue::new(0, 4000, "so what we're going"),
+      Cue::new(2000, 4000, "so what we're going\nto do today is"),
+      Cue::new(4000, 4000, "to do today is look at"),
+      Cue::new(11000, 1000, "look at"),
+    ]
+    .into();
+
+    let text = to_text(&cues, &TextOptions::default());
+    assert_eq!(text, "so what we're going to do today is look at\n\nlook at\n");
+  }
+
+  #[test]
+  fn long_videos_have_hours() {
+    assert_eq!(short_clock(65_000), "01:05");
+    assert_eq!(short_clock(3_725_000), "1:02:05");
+  }
+}
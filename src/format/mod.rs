@@ -9,7 +9,37 @@ pub mod srv3;
 #[cfg(feature = "ttml")]
 pub mod ttml;
 
-#[derive(Debug, Default, Clone, Copy)]
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+mod parse;
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+pub use parse::ParseError;
+
+/// YouTube escapes the texts twice, so entities like `&#39;` are left after deserializing.
+/// Allocates only when there is anything to unescape. A malformed entity leaves the text as is.
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+pub(crate) fn unescape(text: &mut std::borrow::Cow<'_, str>) -> Result<(), quick_xml::escape::EscapeError> {
+  if let unescaped @ std::borrow::Cow::Owned(_) = quick_xml::escape::unescape(text)? {
+    *text = unescaped.into_owned().into();
+  }
+  Ok(())
+}
+
+/// A space between two trimmed texts, unless one of them is a punctuation mark or CJK text.
+#[cfg(any(feature = "srv2", feature = "srv3"))]
+fn separator(prev: Option<char>, next: Option<char>) -> &'static str {
+  let glued = |c: char| {
+    matches!(c, '.' | ',' | '!' | '?' | ';' | ':' | ')' | ']' | '}' | '…' | '»' | '\'')
+      || matches!(c as u32, 0x3000..=0x9fff | 0xf900..=0xfaff | 0xff00..=0xffef)
+  };
+
+  match (prev, next) {
+    (Some(prev), Some(next)) if !prev.is_whitespace() && !next.is_whitespace() && !glued(next) && !glued(prev) => " ",
+    _ => "",
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Format {
   /// Web Video Text Tracks
   VTT,
@@ -42,3 +72,73 @@ impl From<Format> for &'static str {
     }
   }
 }
+
+impl Format {
+  /// Guesses the format of a caption document by its first elements.
+  pub fn detect(document: &str) -> Option<Format> {
+    let document = document.trim_start_matches('\u{feff}').trim_start();
+    let mut end = document.len().min(512);
+    while !document.is_char_boundary(end) {
+      end -= 1;
+    }
+    let head = &document[..end];
+
+    match head {
+      _ if head.starts_with("WEBVTT") => Some(Format::VTT),
+      _ if head.starts_with('{') => Some(Format::JSON3),
+      _ if head.contains(r#"<timedtext format="3""#) => Some(Format::SRV3),
+      _ if head.contains("<timedtext") => Some(Format::SRV2),
+      _ if head.contains("<transcript") => Some(Format::SRV1),
+      _ if head.contains("<tt ") || head.contains("<tt>") => Some(Format::TTML),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn formats_are_detected() {
+    assert_eq!(Format::detect("\u{feff}WEBVTT\n\n"), Some(Format::VTT));
+    assert_eq!(Format::detect(r#"<?xml version="1.0" ?><timedtext format="3"><body/>"#), Some(Format::SRV3));
+    assert_eq!(Format::detect(&format!("<transcript>x{}", "é".repeat(600))), Some(Format::SRV1));
+    assert_eq!(Format::detect(&"é".repeat(600)), None);
+  }
+
+  #[cfg(all(feature = "srv1", feature = "srv2", feature = "srv3"))]
+  #[test]
+  fn hostile_documents_are_not_a_panic() {
+    use crate::cue::Cues;
+
+    let late = r#"<p t="4294967000" d="1000">x</p>"#;
+    let huge = "18446744073709552";
+    let documents = [
+      format!("<transcript>x{}", "é".repeat(600)),
+      late.to_owned(),
+      format!(r#"<timedtext format="3"><head/><body>{late}</body></timedtext>"#),
+      r#"<timedtext><text t="4294967000" d="4294967295">x</text></timedtext>"#.to_owned(),
+      r#"<transcript><text start="4294967.295" dur="4294967.295">x</text></transcript>"#.to_owned(),
+      format!(r#"<transcript><text start="{huge}" dur="{huge}">x</text><text start="1" dur="1">y</text></transcript>"#),
+    ];
+    for document in &documents {
+      Format::detect(document);
+      for format in [Format::SRV1, Format::SRV2, Format::SRV3] {
+        if let Ok(cues) = Cues::parse(document, format) {
+          crate::export::to_srt(&cues);
+        }
+      }
+    }
+
+    let cues = Cues::parse(&documents[2], Format::SRV3).unwrap();
+    assert_eq!(cues[0].end_millis(), u32::MAX);
+
+    let error = srv1::Transcript::parse(&documents[5]).unwrap_err();
+    assert!(error.path.ends_with("/@start"), "{}", error.path);
+    let (transcript, warnings) = srv1::Transcript::parse_lenient(&documents[5]);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(transcript.segments.len(), 1);
+    assert_eq!(transcript.segments[0].value, "y");
+  }
+}
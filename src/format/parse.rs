@@ -0,0 +1,246 @@
+use std::fmt;
+use std::rc::Rc;
+#[cfg(feature = "srv3")]
+use std::str::FromStr;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+
+/// Where and why a document could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, thiserror::Error)]
+#[error("line {line}, column {column}, {path}: {message}")]
+pub struct ParseError {
+  /// Counted from 1.
+  pub line: usize,
+  /// Counted from 1, in characters.
+  pub column: usize,
+  /// The element and the attribute, like `/timedtext/body/p[3]/@t`.
+  pub path: String,
+  pub message: String,
+}
+
+/// Stops at the first error of a strict parse, or collects them as warnings of a lenient one.
+pub(crate) struct Errors {
+  lenient: bool,
+  pub(crate) warnings: Vec<ParseError>,
+}
+
+impl Errors {
+  pub(crate) fn strict() -> Self {
+    Self { lenient: false, warnings: vec![] }
+  }
+
+  pub(crate) fn lenient() -> Self {
+    Self { lenient: true, warnings: vec![] }
+  }
+
+  /// The error is returned only in the strict mode.
+  pub(crate) fn report(&mut self, err: ParseError) -> Result<(), ParseError> {
+    if self.lenient {
+      self.warnings.push(err);
+      Ok(())
+    } else {
+      Err(err)
+    }
+  }
+
+  /// The value, or `None` after reporting the error.
+  pub(crate) fn check<T>(&mut self, result: Result<T, ParseError>) -> Result<Option<T>, ParseError> {
+    match result {
+      Ok(it) => Ok(Some(it)),
+      Err(err) => self.report(err).map(|_| None),
+    }
+  }
+}
+
+/// An element cut out of the document, to be deserialized on its own.
+pub(crate) struct Piece<'a> {
+  pub(crate) name: &'a str,
+  xml: &'a str,
+  input: &'a str,
+  offset: usize,
+  parent: Rc<str>,
+  /// Among the siblings with the same name, counted from 1.
+  index: usize,
+}
+
+impl<'a> Piece<'a> {
+  /// Tracking the path allocates every field name, so it is done only again after a failure.
+  pub(crate) fn deserialize<T: Deserialize<'a>>(&self) -> Result<T, ParseError> {
+    if let Ok(it) = quick_xml::de::from_str(self.xml) {
+      return Ok(it);
+    }
+    let de = &mut quick_xml::de::Deserializer::from_str(self.xml);
+    serde_path_to_error::deserialize(de).map_err(|err| {
+      let path = match err.path().to_string() {
+        inner if inner == "." => self.path(),
+        inner => format!("{}/{inner}", self.path()),
+      };
+      error(self.input, self.offset, path, err.inner())
+    })
+  }
+
+  pub(crate) fn error(&self, message: impl fmt::Display) -> ParseError {
+    error(self.input, self.offset, self.path(), message)
+  }
+
+  fn path(&self) -> String {
+    format!("{}/{}[{}]", self.parent, self.name, self.index)
+  }
+}
+
+/// The root element and the elements picked by the names of their ancestors and their own.
+pub(crate) struct Outline<'a> {
+  input: &'a str,
+  root: Option<(usize, BytesStart<'a>)>,
+  /// Names of the root children, which are not pieces.
+  sections: Vec<&'a str>,
+  pub(crate) pieces: Vec<Piece<'a>>,
+}
+
+/// An open element with the number of its children by name.
+struct Frame<'a> {
+  path: Rc<str>,
+  children: Vec<(&'a str, usize)>,
+}
+
+impl<'a> Outline<'a> {
+  /// A syntax error ends the outline, the pieces before it are kept in the lenient mode.
+  pub(crate) fn new(
+    input: &'a str,
+    wanted: impl Fn(&[&str], &str) -> bool,
+    errors: &mut Errors,
+  ) -> Result<Self, ParseError> {
+    let mut reader = Reader::from_str(input);
+    let mut outline = Outline { input, root: None, sections: vec![], pieces: vec![] };
+    let mut open: Vec<Frame> = vec![];
+    let mut ancestors: Vec<&str> = vec![];
+    let path = |open: &[Frame]| open.last().map_or_else(|| "/".into(), |it| it.path.to_string());
+
+    loop {
+      let offset = reader.buffer_position();
+      let event = match reader.read_event() {
+        Ok(event) => event,
+        Err(err) => {
+          errors.report(error(input, reader.buffer_position(), path(&open), err))?;
+          return Ok(outline);
+        }
+      };
+      let (start, is_empty) = match event {
+        Event::Start(start) => (start, false),
+        Event::Empty(start) => (start, true),
+        Event::End(_) => {
+          open.pop();
+          ancestors.pop();
+          continue;
+        }
+        Event::Eof if outline.root.is_none() => {
+          errors.report(error(input, offset, "/".into(), "the document has no root element"))?;
+          return Ok(outline);
+        }
+        Event::Eof => return Ok(outline),
+        _ => continue,
+      };
+
+      // the event starts with `<` and the name, so the name is borrowed from the input
+      let name_len = start.name().as_ref().len();
+      let Some(name) = input.get(offset + 1..offset + 1 + name_len) else {
+        errors.report(error(input, offset, path(&open), "the element name is not valid"))?;
+        return Ok(outline);
+      };
+
+      let index = match open.last_mut() {
+        Some(parent) => match parent.children.iter_mut().find(|(it, _)| *it == name) {
+          Some((_, count)) => {
+            *count += 1;
+            *count
+          }
+          None => {
+            parent.children.push((name, 1));
+            1
+          }
+        },
+        None => 1,
+      };
+
+      if !ancestors.is_empty() && wanted(&ancestors, name) {
+        if !is_empty {
+          if let Err(err) = reader.read_to_end(start.name()) {
+            errors.report(error(input, reader.buffer_position(), path(&open), err))?;
+            return Ok(outline);
+          }
+        }
+        let xml = &input[offset..reader.buffer_position()];
+        let parent = open.last().map(|it| it.path.clone()).unwrap_or_else(|| "".into());
+        outline.pieces.push(Piece { name, xml, input, offset, parent, index });
+      } else {
+        let path = match open.last() {
+          Some(parent) => format!("{}/{name}[{index}]", parent.path),
+          None => format!("/{name}"),
+        };
+        if open.len() == 1 {
+          outline.sections.push(name);
+        }
+        if !is_empty {
+          open.push(Frame { path: path.into(), children: vec![] });
+          ancestors.push(name);
+        }
+        if open.len() <= 1 && outline.root.is_none() {
+          outline.root = Some((offset, start));
+        }
+      }
+    }
+  }
+
+  /// Fails, unless the root element has the name. A document without the root is reported by [`Outline::new`].
+  pub(crate) fn expect_root(&self, name: &str) -> Result<(), ParseError> {
+    let Some((offset, root)) = &self.root else { return Ok(()) };
+    let found = String::from_utf8_lossy(root.name().as_ref()).into_owned();
+    if found == name {
+      return Ok(());
+    }
+    Err(error(self.input, *offset, format!("/{found}"), format!("expected the root element <{name}>, found <{found}>")))
+  }
+
+  /// Fails, unless the root element has a child with the name.
+  #[cfg(feature = "srv3")]
+  pub(crate) fn expect_section(&self, name: &str) -> Result<(), ParseError> {
+    let Some((offset, root)) = &self.root else { return Ok(()) };
+    if self.sections.contains(&name) {
+      return Ok(());
+    }
+    let path = format!("/{}", String::from_utf8_lossy(root.name().as_ref()));
+    Err(error(self.input, *offset, path, format!("the <{name}> element is missing")))
+  }
+
+  /// An attribute of the root element, which must be there.
+  #[cfg(feature = "srv3")]
+  pub(crate) fn attribute<T: FromStr>(&self, name: &str) -> Result<T, ParseError>
+  where
+    T::Err: fmt::Display,
+  {
+    let Some((offset, root)) = &self.root else {
+      return Err(error(self.input, 0, "/".into(), "the document has no root element"));
+    };
+    let path = format!("/{}/@{name}", String::from_utf8_lossy(root.name().as_ref()));
+    let error = |message: &dyn fmt::Display| error(self.input, *offset, path.clone(), message);
+    let value = match root.try_get_attribute(name) {
+      Ok(Some(it)) => it.unescape_value().map_err(|err| error(&err))?,
+      Ok(None) => return Err(error(&"missing attribute")),
+      Err(err) => return Err(error(&err)),
+    };
+    value.parse().map_err(|err| error(&err))
+  }
+}
+
+fn error(input: &str, offset: usize, path: String, message: impl fmt::Display) -> ParseError {
+  let mut offset = offset.min(input.len());
+  while !input.is_char_boundary(offset) {
+    offset -= 1;
+  }
+  let before = &input[..offset];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().map_or(0, |it| it.chars().count()) + 1;
+  ParseError { line, column, path, message: message.to_string() }
+}
@@ -1,33 +1,76 @@
 use std::borrow::Cow;
 use std::str::FromStr;
 
-use quick_xml::escape::unescape as unescape_xml;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Transcript {
-  #[serde(rename(deserialize = "text"))]
-  pub segments: Vec<TextSegment>,
+use super::parse::{Errors, Outline};
+use super::ParseError;
+use crate::time::{Duration, Timestamp};
+
+/// Texts borrow from the parsed document, unless they had to be unescaped, see [`Transcript::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Transcript<'a> {
+  #[serde(borrow, rename(deserialize = "text"))]
+  pub segments: Vec<TextSegment<'a>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct TextSegment {
-  #[serde(rename(deserialize = "@start"))]
-  pub start_secs: f32,
-  #[serde(rename(deserialize = "@dur"))]
-  pub duration_secs: f32,
-  #[serde(rename(deserialize = "$value"))]
-  pub value: String,
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TextSegment<'a> {
+  /// Stored in seconds, like `4.333`.
+  #[serde(rename(deserialize = "@start"), deserialize_with = "Timestamp::deserialize_secs")]
+  pub start: Timestamp,
+  #[serde(rename(deserialize = "@dur"), deserialize_with = "Duration::deserialize_secs")]
+  pub duration: Duration,
+  #[serde(default, borrow, rename(deserialize = "$value"))]
+  pub value: Cow<'a, str>,
 }
 
-impl TextSegment {
-  pub fn end_secs(&self) -> f32 {
-    self.start_secs + self.duration_secs
+impl TextSegment<'_> {
+  pub fn end(&self) -> Timestamp {
+    self.start + self.duration
+  }
+
+  pub fn into_owned(self) -> TextSegment<'static> {
+    TextSegment { start: self.start, duration: self.duration, value: self.value.into_owned().into() }
   }
 }
 
-impl IntoIterator for Transcript {
-  type Item = TextSegment;
+impl<'a> Transcript<'a> {
+  /// Parses the document without copying the texts, see [`Transcript::into_owned`].
+  /// Fails at the first invalid segment or entity.
+  pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+    Self::parse_with(input, &mut Errors::strict())
+  }
+
+  /// Skips invalid segments and keeps the texts with invalid entities as they are, telling about both.
+  pub fn parse_lenient(input: &'a str) -> (Self, Vec<ParseError>) {
+    let mut errors = Errors::lenient();
+    let transcript = Self::parse_with(input, &mut errors).expect("lenient errors are warnings");
+    (transcript, errors.warnings)
+  }
+
+  fn parse_with(input: &'a str, errors: &mut Errors) -> Result<Self, ParseError> {
+    let outline = Outline::new(input, |ancestors, name| ancestors.len() == 1 && name == "text", errors)?;
+    errors.check(outline.expect_root("transcript"))?;
+    let mut segments = Vec::with_capacity(outline.pieces.len());
+    for piece in &outline.pieces {
+      let Some(mut segment) = errors.check(piece.deserialize::<TextSegment>())? else { continue };
+      if let Err(err) = super::unescape(&mut segment.value) {
+        errors.report(piece.error(err))?;
+      }
+      segments.push(segment);
+    }
+    Ok(Self { segments })
+  }
+
+  /// Copies the borrowed texts, so the transcript outlives the document.
+  pub fn into_owned(self) -> Transcript<'static> {
+    Transcript { segments: self.segments.into_iter().map(TextSegment::into_owned).collect() }
+  }
+}
+
+impl<'a> IntoIterator for Transcript<'a> {
+  type Item = TextSegment<'a>;
   type IntoIter = std::vec::IntoIter<Self::Item>;
 
   fn into_iter(self) -> Self::IntoIter {
@@ -35,25 +78,10 @@ impl IntoIterator for Transcript {
   }
 }
 
-impl FromStr for Transcript {
-  type Err = quick_xml::DeError;
+impl FromStr for Transcript<'static> {
+  type Err = ParseError;
 
   fn from_str(input: &str) -> Result<Self, Self::Err> {
-    #[cfg(not(test))]
-    let mut transcript: Transcript = quick_xml::de::from_str(input)?;
-
-    #[cfg(test)]
-    let mut transcript: Transcript = {
-      let de = &mut quick_xml::de::Deserializer::from_str(input);
-      serde_path_to_error::deserialize(de).unwrap()
-    };
-
-    for it in &mut transcript.segments {
-      if let unescaped @ Cow::Owned(_) = unescape_xml(&it.value).unwrap() {
-        it.value = unescaped.into_owned();
-      };
-    }
-
-    Ok(transcript)
+    Transcript::parse(input).map(Transcript::into_owned)
   }
 }
@@ -1,69 +1,106 @@
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::str::FromStr;
 
-use quick_xml::escape::unescape as unescape_xml;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use super::parse::{Errors, Outline};
+use super::ParseError;
+use crate::cue::{Cue, Cues};
+use crate::time::{Duration, Timestamp};
+
+/// Texts borrow from the parsed document, unless they had to be unescaped, see [`Transcript::parse`].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Transcript {
-  #[serde(rename = "$value")]
-  pub elements: Vec<Element>,
+pub struct Transcript<'a> {
+  #[serde(borrow, rename = "$value")]
+  pub elements: Vec<Element<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
-pub enum Element {
-  Text(TextSegment),
+pub enum Element<'a> {
+  #[serde(borrow)]
+  Text(TextSegment<'a>),
   Window(Window),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct TextSegment {
+pub struct TextSegment<'a> {
   #[serde(rename(deserialize = "@t"))]
-  pub timestamp_millis: u32,
+  pub start: Timestamp,
   #[serde(default, rename(deserialize = "@d"))]
-  pub duration_millis: u32,
+  pub duration: Duration,
+  /// Continues the last row of the window instead of starting a new one.
   #[serde(default, rename(deserialize = "@append"))]
   pub append: bool,
+  /// The last defined window, if not set.
+  #[serde(default, rename(deserialize = "@w"))]
+  pub window_id: Option<u32>,
   #[deprecated(note = "if you know what this field is about, please, raise an issue")] // todo: paste a link to github
   #[serde(default, rename(deserialize = "@r"))]
   pub r: u32,
   #[deprecated(note = "if you know what this field is about, please, raise an issue")] // todo: paste a link to github
   #[serde(default, rename(deserialize = "@c"))]
   pub c: u32,
-  #[serde(default, rename(deserialize = "$value"))]
-  pub value: String,
+  #[serde(default, borrow, rename(deserialize = "$value"))]
+  pub value: Cow<'a, str>,
 }
 
+/// An operation on a window. Only the attributes given by the operation are set.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Window {
   #[serde(rename(deserialize = "@id"))]
   pub id: u32,
-  // todo: may have values "define", ...?
   #[serde(rename(deserialize = "@op"))]
-  pub operation: String,
+  pub operation: WindowOp,
   #[serde(rename(deserialize = "@t"))]
-  pub timestamp_millis: u32,
-  #[serde(rename(deserialize = "@ap"))]
-  pub anchor_point: AnchorPoint,
+  pub start: Timestamp,
+  #[serde(default, rename(deserialize = "@ap"))]
+  pub anchor_point: Option<AnchorPoint>,
   /// X from left
-  #[serde(rename(deserialize = "@ah"))]
-  pub horizontal_alignment: u32,
+  #[serde(default, rename(deserialize = "@ah"))]
+  pub horizontal_alignment: Option<u32>,
   /// Y from top
-  #[serde(rename(deserialize = "@av"))]
-  pub vertical_alignment: u32,
-  #[serde(rename(deserialize = "@rc"))]
-  pub rows_total: u8,
+  #[serde(default, rename(deserialize = "@av"))]
+  pub vertical_alignment: Option<u32>,
+  /// Older rows scroll out, when a new one doesn't fit.
+  #[serde(default, rename(deserialize = "@rc"))]
+  pub rows_total: Option<u8>,
   /// Each column has en-dash width
-  #[serde(rename(deserialize = "@cc"))]
-  pub columns_total: u8,
-  #[serde(rename(deserialize = "@sd"))]
-  pub scroll_direction: ScrollDirection,
+  #[serde(default, rename(deserialize = "@cc"))]
+  pub columns_total: Option<u8>,
+  #[serde(default, rename(deserialize = "@sd"))]
+  pub scroll_direction: Option<ScrollDirection>,
   #[serde(default, rename(deserialize = "@pd"))]
-  pub print_direction: PrintDirection,
-  #[serde(rename(deserialize = "@ju"))]
-  pub text_alignment: TextAlignment,
+  pub print_direction: Option<PrintDirection>,
+  #[serde(default, rename(deserialize = "@ju"))]
+  pub text_alignment: Option<TextAlignment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowOp {
+  /// Creates the window, or resets an existing one.
+  Define,
+  /// Changes the attributes of the window, keeping its rows.
+  Set,
+  /// Removes the rows of the window.
+  Clear,
+  /// Removes the window with its rows.
+  Delete,
+  /// An operation this crate doesn't know, ignored when the screens are built.
+  #[serde(other)]
+  Unknown,
+}
+
+/// The rows of every window shown between two instants, see [`Transcript::screens`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Screen {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  /// Non-empty rows by the window id, from the top to the bottom.
+  pub windows: BTreeMap<u32, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
@@ -105,6 +142,8 @@ pub enum PrintDirection {
   VerticalRtl = 3,
 }
 
+/// Where a new row is added: at the bottom for [`ScrollDirection::LTR`], scrolling the rows up,
+/// or at the top for [`ScrollDirection::RTL`], scrolling them down.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum ScrollDirection {
@@ -113,27 +152,213 @@ pub enum ScrollDirection {
   RTL = 1,
 }
 
-impl FromStr for Transcript {
-  type Err = quick_xml::DeError;
+/// A window during a replay of the events.
+#[derive(Default)]
+struct WindowState<'a> {
+  rows_total: Option<u8>,
+  scroll_direction: ScrollDirection,
+  /// Every row is made of texts shown until their deadlines, with the separators put before them.
+  rows: VecDeque<Vec<(&'static str, &'a str, u32)>>,
+}
 
-  fn from_str(input: &str) -> Result<Self, Self::Err> {
-    #[cfg(not(test))]
-    let mut transcript: Transcript = quick_xml::de::from_str(input)?;
+impl<'a> Transcript<'a> {
+  /// Parses the document without copying the texts, see [`Transcript::into_owned`].
+  /// Fails at the first invalid element or entity.
+  pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+    Self::parse_with(input, &mut Errors::strict())
+  }
+
+  /// Skips invalid elements and keeps the texts with invalid entities as they are, telling about both.
+  pub fn parse_lenient(input: &'a str) -> (Self, Vec<ParseError>) {
+    let mut errors = Errors::lenient();
+    let transcript = Self::parse_with(input, &mut errors).expect("lenient errors are warnings");
+    (transcript, errors.warnings)
+  }
+
+  fn parse_with(input: &'a str, errors: &mut Errors) -> Result<Self, ParseError> {
+    let wanted = |ancestors: &[&str], name: &str| ancestors.len() == 1 && matches!(name, "text" | "window");
+    let outline = Outline::new(input, wanted, errors)?;
+    errors.check(outline.expect_root("timedtext"))?;
+    let mut elements = Vec::with_capacity(outline.pieces.len());
+    for piece in &outline.pieces {
+      let element = match piece.name {
+        "text" => piece.deserialize().map(Element::Text),
+        _ => piece.deserialize().map(Element::Window),
+      };
+      let Some(mut element) = errors.check(element)? else { continue };
+      if let Element::Text(it) = &mut element {
+        if let Err(err) = super::unescape(&mut it.value) {
+          errors.report(piece.error(err))?;
+        }
+      }
+      elements.push(element);
+    }
+    Ok(Self { elements })
+  }
+
+  /// Copies the borrowed texts, so the transcript outlives the document.
+  pub fn into_owned(self) -> Transcript<'static> {
+    let element = |it| match it {
+      Element::Text(it) => Element::Text(it.into_owned()),
+      Element::Window(it) => Element::Window(it),
+    };
+    Transcript { elements: self.elements.into_iter().map(element).collect() }
+  }
+
+  /// Replays the windows and the texts, telling what is on the screen and when it changes.
+  ///
+  /// A text stays in its row until its duration is over, the row scrolls out, or the window is cleared.
+  /// Texts without a duration stay until one of the latter, or till the end, when the last screen ends at `u32::MAX`.
+  pub fn screens(&self) -> Vec<Screen> {
+    let start = |it: &Element| match it {
+      Element::Text(it) => it.start.as_millis(),
+      Element::Window(it) => it.start.as_millis(),
+    };
+    let mut events: Vec<_> = self.elements.iter().collect();
+    events.sort_by_key(|it| start(it));
+
+    let deadlines = events.iter().filter_map(|it| match it {
+      Element::Text(it) if !it.duration.is_zero() => Some(it.end().as_millis()),
+      _ => None,
+    });
+    let instants: BTreeSet<_> = events.iter().map(|it| start(it)).chain(deadlines).collect();
+
+    let mut windows: BTreeMap<u32, WindowState> = BTreeMap::new();
+    let mut last_window = 0;
+    let mut screens: Vec<Screen> = vec![];
+    let mut events = events.into_iter().peekable();
+
+    for now in instants {
+      while let Some(event) = events.next_if(|it| start(it) <= now) {
+        match event {
+          Element::Window(it) => {
+            last_window = it.id;
+            it.apply(windows.entry(it.id).or_default());
+            if it.operation == WindowOp::Delete {
+              windows.remove(&it.id);
+            }
+          }
+          Element::Text(it) => it.apply(windows.entry(it.window_id.unwrap_or(last_window)).or_default()),
+        }
+      }
+
+      for window in windows.values_mut() {
+        for row in &mut window.rows {
+          row.retain(|(_, _, deadline)| *deadline > now);
+        }
+        window.rows.retain(|it| !it.is_empty());
+      }
+
+      let shown: BTreeMap<_, _> = windows
+        .iter()
+        .filter(|(_, it)| !it.rows.is_empty())
+        .map(|(id, it)| (*id, it.rows.iter().map(|row| WindowState::text(row)).collect()))
+        .collect();
+
+      match screens.last_mut() {
+        Some(last) if last.end_millis == u32::MAX && last.windows == shown => continue,
+        Some(last) if last.end_millis == u32::MAX => last.end_millis = now,
+        _ => {}
+      }
+      if !shown.is_empty() {
+        screens.push(Screen { start_millis: now, end_millis: u32::MAX, windows: shown });
+      }
+    }
+    screens
+  }
 
-    #[cfg(test)]
-    let mut transcript: Transcript = {
-      let de = &mut quick_xml::de::Deserializer::from_str(input);
-      serde_path_to_error::deserialize(de).unwrap()
+  /// Cues that replace each other, one per [`Screen`], instead of rolling rows repeating the same text.
+  pub fn pop_on_cues(&self) -> Cues {
+    let cue = |it: Screen| {
+      let rows: Vec<_> = it.windows.into_values().flatten().collect();
+      Cue::new(it.start_millis, it.end_millis - it.start_millis, rows.join("\n"))
     };
+    self.screens().into_iter().map(cue).collect()
+  }
+}
+
+impl Window {
+  fn apply(&self, window: &mut WindowState) {
+    match self.operation {
+      WindowOp::Define => *window = WindowState::default(),
+      WindowOp::Clear | WindowOp::Delete => window.rows.clear(),
+      WindowOp::Set => {}
+      WindowOp::Unknown => return,
+    }
+    if let Some(rows_total) = self.rows_total {
+      window.rows_total = Some(rows_total);
+    }
+    if let Some(scroll_direction) = self.scroll_direction {
+      window.scroll_direction = scroll_direction;
+    }
+    window.scroll();
+  }
+}
+
+impl TextSegment<'_> {
+  #[allow(deprecated)]
+  pub fn into_owned(self) -> TextSegment<'static> {
+    TextSegment {
+      start: self.start,
+      duration: self.duration,
+      append: self.append,
+      window_id: self.window_id,
+      r: self.r,
+      c: self.c,
+      value: self.value.into_owned().into(),
+    }
+  }
 
-    for it in &mut transcript.elements {
-      if let Element::Text(it) = it {
-        if let unescaped @ Cow::Owned(_) = unescape_xml(&it.value).unwrap() {
-          it.value = unescaped.into_owned();
-        };
+  pub fn end(&self) -> Timestamp {
+    self.start + self.duration
+  }
+
+  fn apply<'a>(&'a self, window: &mut WindowState<'a>) {
+    let deadline = if self.duration.is_zero() { u32::MAX } else { self.end().as_millis() };
+    let last = match window.scroll_direction {
+      ScrollDirection::LTR => window.rows.back_mut(),
+      ScrollDirection::RTL => window.rows.front_mut(),
+    };
+    match last {
+      Some(row) if self.append => {
+        let prev = row.last().and_then(|(_, text, _)| text.chars().last());
+        row.push((super::separator(prev, self.value.chars().next()), &self.value, deadline));
+      }
+      _ => {
+        match window.scroll_direction {
+          ScrollDirection::LTR => window.rows.push_back(vec![("", &self.value, deadline)]),
+          ScrollDirection::RTL => window.rows.push_front(vec![("", &self.value, deadline)]),
+        }
+        window.scroll();
       }
     }
+  }
+}
+
+impl WindowState<'_> {
+  /// The texts left in the row. The first one loses its separator, if the texts before it expired.
+  fn text(row: &[(&str, &str, u32)]) -> String {
+    let texts =
+      row.iter().enumerate().flat_map(|(i, (separator, text, _))| [if i == 0 { "" } else { separator }, text]);
+    texts.collect()
+  }
+
+  /// Drops the oldest rows that don't fit.
+  fn scroll(&mut self) {
+    let Some(rows_total) = self.rows_total.map(usize::from) else { return };
+    while self.rows.len() > rows_total.max(1) {
+      match self.scroll_direction {
+        ScrollDirection::LTR => self.rows.pop_front(),
+        ScrollDirection::RTL => self.rows.pop_back(),
+      };
+    }
+  }
+}
+
+impl FromStr for Transcript<'static> {
+  type Err = ParseError;
 
-    Ok(transcript)
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    Transcript::parse(input).map(Transcript::into_owned)
   }
 }
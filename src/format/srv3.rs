@@ -1,24 +1,32 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
-use quick_xml::escape::unescape as unescape_xml;
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use super::parse::{Errors, Outline};
+use super::ParseError;
+use crate::color::Rgba;
+use crate::time::{Duration, Timestamp};
+
 // todo: https://github.com/arcusmaximus/YTSubConverter#ass-feature-support
 // todo: https://jacobstar.medium.com/the-first-complete-guide-to-youtube-captions-f886e06f7d9d
 // todo: https://github.com/arcusmaximus/YTSubConverter/blob/master/ytt.ytt
 
+/// Texts borrow from the parsed document, unless they had to be unescaped, see [`Transcript::parse`].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Transcript {
+pub struct Transcript<'a> {
   pub head: Head,
-  pub body: Body,
+  #[serde(borrow)]
+  pub body: Body<'a>,
   #[serde(rename = "@format")]
   pub format_version: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Head {
   #[serde(default, rename = "pen")]
   pub pens: Vec<Pen>,
@@ -28,7 +36,7 @@ pub struct Head {
   pub window_positioning: Vec<WindowPosition>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Pen {
   #[serde(rename = "@id")]
   pub id: u32,
@@ -39,18 +47,17 @@ pub struct Pen {
   #[serde(default, rename = "@u")]
   pub underline: bool,
 
-  #[serde(default, rename = "@fc")]
-  pub foreground_color: Option<String>,
-  #[serde(default, rename = "@fo")]
-  pub foreground_opacity: Option<u8>,
+  /// `fc` with the `fo` opacity, see [`Pen::foreground`].
+  #[serde(flatten, with = "foreground")]
+  pub foreground_color: Option<Rgba>,
 
-  #[serde(default, rename = "@bc")]
-  pub background_color: Option<String>,
-  #[serde(default, rename = "@bo")]
-  pub background_opacity: Option<u8>,
+  /// `bc` with the `bo` opacity, see [`Pen::background`].
+  #[serde(flatten, with = "background")]
+  pub background_color: Option<Rgba>,
 
-  #[serde(default, rename = "@ec")]
-  pub edge_color: Option<String>,
+  /// Opaque, see [`Pen::edge`].
+  #[serde(default, rename = "@ec", with = "edge")]
+  pub edge_color: Option<Rgba>,
   #[serde(default, rename = "@et")]
   pub edge_type: EdgeType,
 
@@ -67,15 +74,31 @@ pub struct Pen {
   /// virtual percentage of 0 which equates to a real percentage of 75.
   ///
   /// Supported on iOS but not Android.
-  #[serde(default, rename = "@si")]
+  #[serde(default, rename = "@sz")]
   pub font_size_perc: Option<u32>,
 
   /// Vertical text alignment. Not supported on mobile devices.
   #[serde(default, rename = "@of")]
   pub vertical_alignment: Option<VerticalAlignment>,
+
+  /// The role of the span in a ruby annotation, see [`TextSegment::ruby_units`].
+  #[serde(default, rename = "@rb")]
+  pub ruby: RubyPart,
+
+  /// Packs the characters into a single upright cell of vertical text, e.g. two-digit numbers (tate-chu-yoko).
+  #[serde(default, rename = "@hg")]
+  pub horizontal_group: bool,
+
+  /// Marks over every character, e.g. dots emphasizing Japanese text (bouten).
+  #[serde(default, rename = "@te")]
+  pub text_emphasis: TextEmphasis,
+
+  /// Attributes without a field above, keyed by their names with the `@` prefix, kept as is for round-trips.
+  #[serde(flatten)]
+  pub other: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct WindowStyle {
   #[serde(rename = "@id")]
   pub id: u32,
@@ -88,13 +111,13 @@ pub struct WindowStyle {
   pub text_alignment: Option<TextAlignment>,
   #[serde(default, rename = "@mh")]
   pub mode_hint: ModeHint,
-  #[serde(default, rename = "@wfc")]
-  pub fill_color: Option<String>,
-  #[serde(default, rename = "@wfo")]
-  pub fill_opacity: Option<u8>,
+  /// `wfc` with the `wfo` opacity, see [`WindowStyle::fill`].
+  #[serde(flatten, with = "fill")]
+  pub fill_color: Option<Rgba>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// Unset values are up to the player, which puts captions at the bottom center by default.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct WindowPosition {
   #[serde(rename = "@id")]
   pub id: u32,
@@ -131,33 +154,33 @@ pub struct WindowPosition {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Body {
-  #[serde(rename = "$value")]
-  pub elements: Vec<Element>,
+pub struct Body<'a> {
+  #[serde(borrow, rename = "$value")]
+  pub elements: Vec<Element<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub enum Element {
-  #[serde(rename = "p")]
-  Segment(TextSegment),
+pub enum Element<'a> {
+  #[serde(borrow, rename = "p")]
+  Segment(TextSegment<'a>),
   #[serde(rename = "w")]
   Window(Window),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct TextSegment {
+pub struct TextSegment<'a> {
   #[serde(rename = "@t")]
-  pub time_millis: u32,
+  pub start: Timestamp,
   #[serde(default, rename = "@d")]
-  pub duration_millis: u32,
+  pub duration: Duration,
   #[serde(default, rename = "@p")]
   pub pen_id: Option<u32>,
   #[serde(default, rename = "@wp")]
   pub window_position_id: Option<u32>,
   #[serde(default, rename = "@ws")]
   pub window_style_id: Option<u32>,
-  #[serde(default, rename = "$value")]
-  pub value: Vec<Text>,
+  #[serde(default, borrow, rename = "$value")]
+  pub value: Vec<Text<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -165,7 +188,7 @@ pub struct Window {
   #[serde(rename = "@id")]
   pub id: u32,
   #[serde(rename = "@t")]
-  pub time_millis: u32,
+  pub start: Timestamp,
   #[serde(rename = "@wp")]
   pub window_position_id: u32,
   #[serde(rename = "@ws")]
@@ -173,21 +196,58 @@ pub struct Window {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub enum Text {
-  #[serde(rename = "s")]
-  Span(Span),
-  #[serde(rename = "$text")]
-  Str(String),
+pub enum Text<'a> {
+  #[serde(borrow, rename = "s")]
+  Span(Span<'a>),
+  #[serde(borrow, rename = "$text")]
+  Str(Cow<'a, str>),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct Span {
+pub struct Span<'a> {
+  /// Relative to the start of the segment.
   #[serde(default, rename = "@t")]
-  pub relative_time_millis: u32,
+  pub offset: Duration,
   #[serde(rename = "@p")]
   pub pen_id: Option<u32>,
-  #[serde(rename = "$value")]
-  pub value: String,
+  /// Set on every word of automatic captions, which are shown word by word as they are said.
+  /// Mostly `0`, raise an issue, if you know how to interpret the values.
+  #[serde(default, rename = "@ac")]
+  pub asr_confidence: Option<u8>,
+  #[serde(default, borrow, rename = "$value")]
+  pub value: Cow<'a, str>,
+}
+
+/// A piece of text with the pen and the window looked up in [`Head`], see [`Transcript::runs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Run<'a> {
+  /// The segment time plus the span time.
+  pub start_millis: u32,
+  /// The end of the segment.
+  pub end_millis: u32,
+  /// The separator put before the text by [`TextSegment::text`].
+  pub separator: &'static str,
+  pub text: &'a str,
+  /// The span pen, or the segment pen, or the default one.
+  pub pen: &'a Pen,
+  pub window_style: &'a WindowStyle,
+  pub window_position: &'a WindowPosition,
+}
+
+/// A base text with its reading, e.g. kanji with furigana, or a plain run without a reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RubyUnit<'a> {
+  pub base: Run<'a>,
+  /// Shown above or below the base, depending on [`Pen::ruby`] of its run.
+  pub annotation: Option<Run<'a>>,
+}
+
+/// A reference from the body to the [`Head`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HeadRef {
+  Pen(u32),
+  WindowStyle(u32),
+  WindowPosition(u32),
 }
 
 /// <pre>
@@ -236,15 +296,18 @@ pub enum TextAlignment {
   Justify = 3,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
-#[repr(u8)]
+/// The shadow or the outline of the text, drawn in [`Pen::edge`] color.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
 pub enum EdgeType {
   #[default]
-  None = 0,
-  HardShadow = 1,
-  Bevel = 2,
-  GlowOutline = 3,
-  SoftShadow = 4,
+  None,
+  HardShadow,
+  Bevel,
+  GlowOutline,
+  SoftShadow,
+  /// Kept as is, so newer documents still parse.
+  Unknown(u8),
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
@@ -278,20 +341,35 @@ pub enum VerticalAlignment {
   Superscript = 2,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
-#[repr(u8)]
+/// Shown above horizontal text and to the right of vertical text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum TextEmphasis {
+  #[default]
+  None,
+  FilledCircle,
+  OpenCircle,
+  FilledSesame,
+  OpenSesame,
+  /// Kept as is, so newer documents still parse.
+  Unknown(u8),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
 pub enum RubyPart {
   #[default]
-  None = 0,
+  None,
   /// for kanji spans
-  Base = 1,
+  Base,
   /// for clients that don't support ruby
-  Parenthesis = 2,
-  // todo: 3?
+  Parenthesis,
   /// for furigana spans
-  TextBefore = 4,
+  TextBefore,
   /// for furigana spans
-  TextAfter = 5,
+  TextAfter,
+  /// Kept as is, like `3`, which has no known meaning.
+  Unknown(u8),
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize_repr, Deserialize_repr)]
@@ -303,18 +381,45 @@ pub enum ModeHint {
   Scroll = 2,
 }
 
-impl Deref for Text {
-  type Target = String;
+/// Enums with values YouTube may extend, so unknown values are kept instead of failing the document.
+macro_rules! open_enum {
+  ($name:ident { $($variant:ident = $value:literal),* $(,)? }) => {
+    impl From<u8> for $name {
+      fn from(value: u8) -> Self {
+        match value {
+          $($value => $name::$variant,)*
+          other => $name::Unknown(other),
+        }
+      }
+    }
+
+    impl From<$name> for u8 {
+      fn from(value: $name) -> Self {
+        match value {
+          $($name::$variant => $value,)*
+          $name::Unknown(other) => other,
+        }
+      }
+    }
+  };
+}
+
+open_enum!(EdgeType { None = 0, HardShadow = 1, Bevel = 2, GlowOutline = 3, SoftShadow = 4 });
+open_enum!(TextEmphasis { None = 0, FilledCircle = 1, OpenCircle = 2, FilledSesame = 3, OpenSesame = 4 });
+open_enum!(RubyPart { None = 0, Base = 1, Parenthesis = 2, TextBefore = 4, TextAfter = 5 });
+
+impl<'a> Deref for Text<'a> {
+  type Target = Cow<'a, str>;
 
   fn deref(&self) -> &Self::Target {
     match self {
       Text::Span(span) => &span.value,
-      Text::Str(value) => &value,
+      Text::Str(value) => value,
     }
   }
 }
 
-impl DerefMut for Text {
+impl DerefMut for Text<'_> {
   fn deref_mut(&mut self) -> &mut Self::Target {
     match self {
       Text::Span(span) => &mut span.value,
@@ -323,35 +428,313 @@ impl DerefMut for Text {
   }
 }
 
-impl AsRef<str> for Text {
+impl Text<'_> {
+  pub fn into_owned(self) -> Text<'static> {
+    match self {
+      Text::Span(span) => Text::Span(Span { value: span.value.into_owned().into(), ..span }),
+      Text::Str(value) => Text::Str(value.into_owned().into()),
+    }
+  }
+}
+
+impl AsRef<str> for Text<'_> {
   fn as_ref(&self) -> &str {
     self.deref().as_ref()
   }
 }
 
-impl FromStr for Transcript {
-  type Err = quick_xml::DeError;
+lazy_static! {
+  static ref DEFAULT_PEN: Pen = Pen::default();
+  static ref DEFAULT_WINDOW_STYLE: WindowStyle = WindowStyle::default();
+  static ref DEFAULT_WINDOW_POSITION: WindowPosition = WindowPosition::default();
+}
 
-  fn from_str(input: &str) -> Result<Self, Self::Err> {
-    #[cfg(not(test))]
-    let mut transcript: Transcript = quick_xml::de::from_str(input)?;
+impl Head {
+  /// The pen with the id, or the default one for `None`.
+  pub fn pen(&self, id: Option<u32>) -> crate::Result<&Pen> {
+    let Some(id) = id else { return Ok(&DEFAULT_PEN) };
+    self.pens.iter().find(|it| it.id == id).ok_or(crate::Error::UndefinedInHead(HeadRef::Pen(id)))
+  }
+
+  /// The window style with the id, or the default one for `None`.
+  pub fn window_style(&self, id: Option<u32>) -> crate::Result<&WindowStyle> {
+    let Some(id) = id else { return Ok(&DEFAULT_WINDOW_STYLE) };
+    self.window_styling.iter().find(|it| it.id == id).ok_or(crate::Error::UndefinedInHead(HeadRef::WindowStyle(id)))
+  }
 
-    #[cfg(test)]
-    let mut transcript: Transcript = {
-      let de = &mut quick_xml::de::Deserializer::from_str(input);
-      serde_path_to_error::deserialize(de).unwrap()
+  /// The window position with the id, or the default one for `None`.
+  pub fn window_position(&self, id: Option<u32>) -> crate::Result<&WindowPosition> {
+    let Some(id) = id else { return Ok(&DEFAULT_WINDOW_POSITION) };
+    let position = self.window_positioning.iter().find(|it| it.id == id);
+    position.ok_or(crate::Error::UndefinedInHead(HeadRef::WindowPosition(id)))
+  }
+}
+
+impl Pen {
+  /// The text color, opaque white by default.
+  pub fn foreground(&self) -> Rgba {
+    self.foreground_color.unwrap_or(DEFAULT_FOREGROUND)
+  }
+
+  /// The color behind the text, translucent black by default.
+  pub fn background(&self) -> Rgba {
+    self.background_color.unwrap_or(DEFAULT_BACKGROUND)
+  }
+
+  /// The color of the [`EdgeType`], as opaque as the text. `None` means the player picks a contrasting one.
+  pub fn edge(&self) -> Option<Rgba> {
+    self.edge_color.map(|it| it.with_opacity(self.foreground().a))
+  }
+}
+
+impl WindowStyle {
+  /// The color of the window behind the captions, transparent by default.
+  pub fn fill(&self) -> Rgba {
+    self.fill_color.unwrap_or(DEFAULT_FILL)
+  }
+}
+
+const DEFAULT_FOREGROUND: Rgba = Rgba::WHITE;
+const DEFAULT_BACKGROUND: Rgba = Rgba::opaque(0x08, 0x08, 0x08).with_opacity(191);
+const DEFAULT_FILL: Rgba = Rgba::BLACK.with_opacity(0);
+
+/// Serde helpers folding a color attribute and its opacity attribute into one [`Rgba`].
+/// An attribute missing in the pair takes the part of the default color.
+macro_rules! color_attributes {
+  ($module:ident, $color:literal, $opacity:literal, $default:ident) => {
+    mod $module {
+      use serde::de::Error as _;
+      use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+      use crate::color::Rgba;
+
+      /// Flattened attributes are buffered as text, so the opacity is parsed here too.
+      #[derive(Serialize, Deserialize)]
+      struct Attributes {
+        #[serde(default, rename = $color, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, rename = $opacity, skip_serializing_if = "Option::is_none")]
+        opacity: Option<String>,
+      }
+
+      pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Rgba>, D::Error> {
+        let Attributes { color, opacity } = Attributes::deserialize(deserializer)?;
+        let color = color.map(|it| it.parse::<Rgba>()).transpose().map_err(D::Error::custom)?;
+        let opacity = match opacity {
+          Some(it) => Some(it.parse::<u8>().map_err(|_| D::Error::custom(format!("{it:?} is not an opacity")))?),
+          None => None,
+        };
+        if color.is_none() && opacity.is_none() {
+          return Ok(None);
+        }
+        Ok(Some(color.unwrap_or(super::$default).with_opacity(opacity.unwrap_or(super::$default.a))))
+      }
+
+      pub fn serialize<S: Serializer>(value: &Option<Rgba>, serializer: S) -> Result<S::Ok, S::Error> {
+        let color = value.map(|it| Rgba::opaque(it.r, it.g, it.b).to_css());
+        Attributes { color, opacity: value.map(|it| it.a.to_string()) }.serialize(serializer)
+      }
+    }
+  };
+}
+
+color_attributes!(foreground, "@fc", "@fo", DEFAULT_FOREGROUND);
+color_attributes!(background, "@bc", "@bo", DEFAULT_BACKGROUND);
+color_attributes!(fill, "@wfc", "@wfo", DEFAULT_FILL);
+
+/// An opaque color attribute.
+mod edge {
+  use serde::de::Error as _;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  use crate::color::Rgba;
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Rgba>, D::Error> {
+    let color = Option::<String>::deserialize(deserializer)?;
+    color.map(|it| it.parse::<Rgba>()).transpose().map_err(D::Error::custom)
+  }
+
+  pub fn serialize<S: Serializer>(value: &Option<Rgba>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.map(|it| Rgba::opaque(it.r, it.g, it.b).to_css()).serialize(serializer)
+  }
+}
+
+impl<'a> Transcript<'a> {
+  /// Parses the document without copying the texts, see [`Transcript::into_owned`].
+  /// Fails at the first invalid element or entity.
+  pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+    Self::parse_with(input, &mut Errors::strict())
+  }
+
+  /// Skips invalid elements and keeps the texts with invalid entities as they are, telling about both.
+  /// The format version is 3, when it is missing.
+  pub fn parse_lenient(input: &'a str) -> (Self, Vec<ParseError>) {
+    let mut errors = Errors::lenient();
+    let transcript = Self::parse_with(input, &mut errors).expect("lenient errors are warnings");
+    (transcript, errors.warnings)
+  }
+
+  fn parse_with(input: &'a str, errors: &mut Errors) -> Result<Self, ParseError> {
+    let wanted = |ancestors: &[&str], name: &str| match ancestors {
+      [_, "head"] => matches!(name, "pen" | "ws" | "wp"),
+      [_, "body"] => matches!(name, "p" | "w"),
+      _ => false,
+    };
+    let outline = Outline::new(input, wanted, errors)?;
+    errors.check(outline.expect_root("timedtext"))?;
+    errors.check(outline.expect_section("head"))?;
+    errors.check(outline.expect_section("body"))?;
+    let format_version = errors.check(outline.attribute("format"))?.unwrap_or(3);
+
+    let mut head = Head::default();
+    let mut elements = vec![];
+    for piece in &outline.pieces {
+      match piece.name {
+        "pen" => head.pens.extend(errors.check(piece.deserialize())?),
+        "ws" => head.window_styling.extend(errors.check(piece.deserialize())?),
+        "wp" => head.window_positioning.extend(errors.check(piece.deserialize())?),
+        "w" => elements.extend(errors.check(piece.deserialize().map(Element::Window))?),
+        _ => {
+          let Some(mut seg) = errors.check(piece.deserialize::<TextSegment>())? else { continue };
+          for text in &mut seg.value {
+            if let Err(err) = super::unescape(text) {
+              errors.report(piece.error(err))?;
+            }
+          }
+          elements.push(Element::Segment(seg));
+        }
+      }
+    }
+    Ok(Self { head, body: Body { elements }, format_version })
+  }
+
+  /// Copies the borrowed texts, so the transcript outlives the document.
+  pub fn into_owned(self) -> Transcript<'static> {
+    let element = |it| match it {
+      Element::Segment(it) => Element::Segment(it.into_owned()),
+      Element::Window(it) => Element::Window(it),
     };
+    let body = Body { elements: self.body.elements.into_iter().map(element).collect() };
+    Transcript { head: self.head, body, format_version: self.format_version }
+  }
 
-    for it in &mut transcript.body.elements {
+  /// Pieces of text of all segments with their styles and positions, so they can be rendered as is.
+  ///
+  /// A span pen replaces the segment pen as a whole, the attributes are not merged.
+  pub fn runs(&self) -> crate::Result<Vec<Run<'_>>> {
+    let mut runs = vec![];
+    for it in &self.body.elements {
       if let Element::Segment(seg) = it {
-        for it in &mut seg.value {
-          if let unescaped @ Cow::Owned(_) = unescape_xml(it).unwrap() {
-            *it.deref_mut() = unescaped.into_owned();
-          };
-        }
+        runs.extend(seg.runs(&self.head)?);
       }
     }
+    Ok(runs)
+  }
+}
+
+impl TextSegment<'_> {
+  pub fn into_owned(self) -> TextSegment<'static> {
+    TextSegment {
+      start: self.start,
+      duration: self.duration,
+      pen_id: self.pen_id,
+      window_position_id: self.window_position_id,
+      window_style_id: self.window_style_id,
+      value: self.value.into_iter().map(Text::into_owned).collect(),
+    }
+  }
+
+  /// Runs grouped into ruby units. A base span is followed by its reading, which is wrapped into parenthesis spans
+  /// for players without ruby support. The parentheses are dropped, while unbalanced parts are left as plain runs.
+  pub fn ruby_units<'a>(&'a self, head: &'a Head) -> crate::Result<Vec<RubyUnit<'a>>> {
+    let units = group_ruby(self.runs(head)?, |it| it.pen.ruby);
+    Ok(units.into_iter().map(|(base, annotation)| RubyUnit { base, annotation }).collect())
+  }
+
+  /// Pieces of text of the segment, see [`Transcript::runs`].
+  pub fn runs<'a>(&'a self, head: &'a Head) -> crate::Result<Vec<Run<'a>>> {
+    let window_style = head.window_style(self.window_style_id)?;
+    let window_position = head.window_position(self.window_position_id)?;
+    let end_millis = self.end().as_millis();
+
+    self
+      .separated()
+      .map(|(separator, it)| {
+        let (offset, pen_id) = match it {
+          Text::Span(span) => (span.offset, span.pen_id.or(self.pen_id)),
+          Text::Str(_) => (Duration::ZERO, self.pen_id),
+        };
+        Ok(Run {
+          start_millis: (self.start + offset).as_millis(),
+          end_millis,
+          separator,
+          text: it.as_ref(),
+          pen: head.pen(pen_id)?,
+          window_style,
+          window_position,
+        })
+      })
+      .collect()
+  }
+
+  pub fn end(&self) -> Timestamp {
+    self.start + self.duration
+  }
+
+  /// Concatenates the spans. The deserializer trims the whitespaces around each span,
+  /// so the words are separated with a space again, unless it is a punctuation mark or CJK text.
+  pub fn text(&self) -> String {
+    self.separated().flat_map(|(separator, it)| [separator, it.as_ref()]).collect()
+  }
+
+  /// Pairs every span with the separator put before it by [`TextSegment::text`].
+  pub fn separated(&self) -> impl Iterator<Item = (&'static str, &Text<'_>)> {
+    let mut prev: Option<char> = None;
+    self.value.iter().map(move |it| {
+      let separator = super::separator(prev, it.chars().next());
+      prev = it.chars().last().or(prev);
+      (separator, it)
+    })
+  }
+}
+
+/// Pairs every ruby base with its reading, see [`TextSegment::ruby_units`].
+pub(crate) fn group_ruby<T>(items: Vec<T>, part: impl Fn(&T) -> RubyPart) -> Vec<(T, Option<T>)> {
+  let parts: Vec<_> = items.iter().map(part).collect();
+  let is_text = |i: usize| matches!(parts.get(i), Some(RubyPart::TextBefore | RubyPart::TextAfter));
+  let is_parenthesis = |i: usize| parts.get(i) == Some(&RubyPart::Parenthesis);
+
+  let mut units = vec![];
+  let mut items = items.into_iter().enumerate();
+  while let Some((i, base)) = items.next() {
+    let annotation = match parts[i] {
+      RubyPart::Base if is_parenthesis(i + 1) && is_text(i + 2) && is_parenthesis(i + 3) => {
+        let text = items.nth(1).map(|(_, it)| it);
+        items.next();
+        text
+      }
+      RubyPart::Base if is_text(i + 1) => items.next().map(|(_, it)| it),
+      _ => None,
+    };
+    units.push((base, annotation));
+  }
+  units
+}
 
-    Ok(transcript)
+impl std::fmt::Display for HeadRef {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HeadRef::Pen(id) => write!(f, "pen {id}"),
+      HeadRef::WindowStyle(id) => write!(f, "window style {id}"),
+      HeadRef::WindowPosition(id) => write!(f, "window position {id}"),
+    }
+  }
+}
+
+impl FromStr for Transcript<'static> {
+  type Err = ParseError;
+
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    Transcript::parse(input).map(Transcript::into_owned)
   }
 }
@@ -0,0 +1,72 @@
+use crate::error::Result;
+use crate::format::srv3;
+
+/// What a segment shows between two instants, as its spans are revealed one by one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Frame<'a> {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  /// The runs revealed so far, each with its own pen.
+  pub runs: Vec<srv3::Run<'a>>,
+}
+
+impl srv3::Transcript<'_> {
+  /// Frames of all segments ordered by the start. Frames of overlapping segments overlap too.
+  pub fn timeline(&self) -> Result<Vec<Frame<'_>>> {
+    let mut frames = vec![];
+    for it in &self.body.elements {
+      if let srv3::Element::Segment(seg) = it {
+        frames.extend(seg.timeline(&self.head)?);
+      }
+    }
+    frames.sort_by_key(|it| it.start_millis);
+    Ok(frames)
+  }
+}
+
+impl srv3::TextSegment<'_> {
+  /// A new frame starts whenever a span appears, see [`srv3::Span::offset`].
+  /// Segments without timed spans are a single frame.
+  pub fn timeline<'a>(&'a self, head: &'a srv3::Head) -> Result<Vec<Frame<'a>>> {
+    let runs = self.runs(head)?;
+    let end_millis = self.end().as_millis();
+
+    let mut starts: Vec<_> = runs.iter().map(|it| it.start_millis).chain([self.start.as_millis()]).collect();
+    starts.sort_unstable();
+    starts.dedup();
+
+    let frames = starts.iter().enumerate().map(|(i, &start_millis)| Frame {
+      start_millis,
+      end_millis: starts.get(i + 1).map_or(end_millis, |&next| next.min(end_millis)),
+      runs: runs.iter().filter(|it| it.start_millis <= start_millis).copied().collect(),
+    });
+    Ok(frames.filter(|it| it.start_millis < it.end_millis && !it.runs.is_empty()).collect())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn spans_are_revealed_one_by_one() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" b="1"/></head>
+      <body>
+        <p t="1000" d="3000"><s>Take</s><s t="500" p="1">this</s><s t="1500">pink</s><s t="5000">ribbon</s></p>
+      </body>
+    </timedtext>"#;
+    let transcript: srv3::Transcript = xml.parse().unwrap();
+
+    let timeline = transcript.timeline().unwrap();
+    let frames: Vec<_> = timeline
+      .iter()
+      .map(|it| (it.start_millis, it.end_millis, it.runs.iter().map(|it| it.text).collect::<Vec<_>>().join(" ")))
+      .collect();
+    assert_eq!(
+      frames,
+      [(1000, 1500, "Take".into()), (1500, 2500, "Take this".into()), (2500, 4000, "Take this pink".into())]
+    );
+    assert!(timeline[1].runs[1].pen.bold);
+  }
+}
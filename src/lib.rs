@@ -1,11 +1,31 @@
+pub mod align;
+pub mod annotation;
+pub mod batch;
+pub mod cache;
+pub mod color;
+pub mod cue;
+pub mod diff;
 mod error;
+pub mod export;
 pub mod format;
+#[cfg(feature = "srv3")]
+pub mod karaoke;
+mod live;
+pub mod playlist;
 mod scraper;
+pub mod search;
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+pub mod stream;
+pub mod time;
+mod timing;
+pub mod validate;
+mod video_id;
 
 #[doc(inline)]
 pub use error::{Error, Result};
 
 pub use scraper::*;
+pub use video_id::VideoId;
 
 pub mod language_tags {
   pub use language_tags::{LanguageTag, ParseError, ValidationError};
@@ -16,12 +36,13 @@ mod tests {
   use language_tags::LanguageTag;
 
   use crate::scraper::DigestScraper;
+  use crate::VideoId;
 
   type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
 
   #[tokio::test]
   async fn it_works() -> Result<()> {
-    let video_id = "JRMOIE_wAFk";
+    let video_id: VideoId = "JRMOIE_wAFk".parse()?;
     let scraper = DigestScraper::new(reqwest::Client::new());
     let digest = scraper.fetch(video_id, None).await?;
     let en = LanguageTag::parse("en")?;
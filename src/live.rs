@@ -0,0 +1,57 @@
+use std::time::{Duration, SystemTime};
+
+use tokio::time::{sleep_until, Instant};
+
+use crate::error::{Error, Result};
+use crate::scraper::{Digest, DigestScraper};
+use crate::video_id::VideoId;
+
+impl DigestScraper {
+  /// Polls the watch page every `interval` until the captions appear, giving up after `timeout`.
+  ///
+  /// Meant for live streams and premieres: upcoming streams are checked again at the scheduled start,
+  /// and finished streams are checked until their automatically generated captions are processed.
+  /// Any other error is returned immediately.
+  ///
+  /// ```no_run
+  /// # async fn run() -> youtube_captions::Result<()> {
+  /// use std::time::Duration;
+  /// use youtube_captions::{DigestScraper, VideoId};
+  ///
+  /// let scraper = DigestScraper::new(reqwest::Client::new());
+  /// let video_id: VideoId = "https://www.youtube.com/live/JRMOIE_wAFk".parse()?;
+  /// let interval = Duration::from_secs(5 * 60);
+  /// let digest = scraper.wait_for_captions(video_id, None, interval, Duration::from_secs(6 * 60 * 60)).await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub async fn wait_for_captions<'a, Str: Into<Option<&'a str>>>(
+    &self,
+    video_id: impl Into<VideoId>,
+    lang: Str,
+    interval: Duration,
+    timeout: Duration,
+  ) -> Result<Digest> {
+    let (video_id, lang) = (video_id.into(), lang.into());
+    let deadline = Instant::now() + timeout;
+
+    loop {
+      let (err, next) = match self.fetch(&video_id, lang).await {
+        Err(err @ Error::LiveStreamUpcoming { scheduled_start: Some(start) }) => {
+          let until_start = start.duration_since(SystemTime::now()).unwrap_or_default();
+          (err, Instant::now() + until_start.max(interval))
+        }
+        Err(
+          err @ (Error::LiveStreamUpcoming { .. } | Error::LiveStreamInProgress | Error::CaptionsNotYetAvailable),
+        ) => (err, Instant::now() + interval),
+        result => return result,
+      };
+
+      if next > deadline {
+        return Err(err);
+      }
+      tracing::debug!("captions of {} are not available yet, retrying in {:?}", video_id, next - Instant::now());
+      sleep_until(next).await;
+    }
+  }
+}
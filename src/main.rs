@@ -0,0 +1,387 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use youtube_captions::align::align;
+use youtube_captions::batch::TrackPolicy;
+use youtube_captions::cue::Cues;
+use youtube_captions::export::TextOptions;
+use youtube_captions::format::Format;
+use youtube_captions::language_tags::LanguageTag;
+use youtube_captions::search::Search;
+use youtube_captions::{export, DigestScraper, Error, VideoId};
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  2  invalid arguments
+  3  the video is unavailable or the link is invalid
+  4  the video has no matching captions
+  5  the captions can't be translated into the language
+  6  YouTube requires solving a captcha or giving consent
+  7  the request to YouTube failed
+  8  the document can't be parsed
+  9  the file can't be read";
+
+#[derive(Parser)]
+#[command(version, about = "Fetches and converts YouTube captions", after_help = EXIT_CODES)]
+struct Cli {
+  /// Print JSON instead of plain text, including errors
+  #[arg(long, global = true)]
+  json: bool,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// List caption tracks of a video
+  List {
+    /// A video id or a link to the video
+    url: String,
+  },
+
+  /// Download a caption track
+  Fetch {
+    /// A video id or a link to the video
+    url: String,
+    /// Language of the track, manually created tracks are preferred over generated ones
+    #[arg(long, default_value = "en", value_parser = parse_lang)]
+    lang: LanguageTag,
+    /// Machine translate the track into the language
+    #[arg(long, value_parser = parse_lang)]
+    translate: Option<LanguageTag>,
+    #[arg(long, value_enum, default_value_t = FetchFormat::Srv1)]
+    format: FetchFormat,
+    /// Remove sound events, music and speaker markers from converted formats
+    #[arg(long)]
+    strip_annotations: bool,
+  },
+
+  /// Find where a phrase is said in a video
+  Search {
+    /// A video id or a link to the video
+    url: String,
+    query: String,
+    #[arg(long, default_value = "en", value_parser = parse_lang)]
+    lang: LanguageTag,
+    /// Treat the query as a regular expression
+    #[arg(long)]
+    regex: bool,
+    #[arg(long)]
+    case_sensitive: bool,
+  },
+
+  /// Show two tracks of a video together, e.g. the original and its translation
+  Dual {
+    /// A video id or a link to the video
+    url: String,
+    #[arg(long, default_value = "en", value_parser = parse_lang)]
+    lang: LanguageTag,
+    /// Language of the second track, machine translated if the video has no such track
+    #[arg(long, value_parser = parse_lang)]
+    second: LanguageTag,
+    /// `--json` always prints json
+    #[arg(long, value_enum, default_value_t = DualFormat::Srt)]
+    format: DualFormat,
+  },
+
+  /// Convert a downloaded srv1, srv2 or srv3 document
+  Convert {
+    file: PathBuf,
+    #[arg(long, value_enum)]
+    to: Target,
+    /// Remove sound events, music and speaker markers
+    #[arg(long)]
+    strip_annotations: bool,
+  },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FetchFormat {
+  Vtt,
+  Ttml,
+  Srv1,
+  Srv2,
+  Srv3,
+  Json3,
+  Srt,
+  Ass,
+  Txt,
+  /// Markdown with chapter headings and links to the video
+  Md,
+  /// HTML with chapter headings and links to the video
+  Html,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+  Srt,
+  Vtt,
+  Ass,
+  Txt,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DualFormat {
+  Srt,
+  Ass,
+  Json,
+}
+
+impl FetchFormat {
+  /// Formats served by YouTube as is, the others are converted from srv1.
+  /// Documents are rendered from srv3 to keep the styling.
+  fn served(self) -> Result<Format, Target> {
+    match self {
+      FetchFormat::Vtt => Ok(Format::VTT),
+      FetchFormat::Ttml => Ok(Format::TTML),
+      FetchFormat::Srv1 => Ok(Format::SRV1),
+      FetchFormat::Srv2 => Ok(Format::SRV2),
+      FetchFormat::Srv3 | FetchFormat::Md | FetchFormat::Html => Ok(Format::SRV3),
+      FetchFormat::Json3 => Ok(Format::JSON3),
+      FetchFormat::Srt => Err(Target::Srt),
+      FetchFormat::Ass => Err(Target::Ass),
+      FetchFormat::Txt => Err(Target::Txt),
+    }
+  }
+}
+
+enum Failure {
+  Captions(Error),
+  File(std::io::Error),
+  UnknownFormat,
+}
+
+impl From<Error> for Failure {
+  fn from(err: Error) -> Self {
+    Failure::Captions(err)
+  }
+}
+
+impl Failure {
+  fn exit_code(&self) -> u8 {
+    let err = match self {
+      Failure::Captions(err) => err,
+      Failure::File(_) => return 9,
+      Failure::UnknownFormat => return 8,
+    };
+
+    match err {
+      Error::InvalidPattern(_) => 2,
+      Error::InvalidVideoId(_) | Error::VideoUnavailable | Error::InvalidPlaylist(_) | Error::PlaylistUnavailable => 3,
+      Error::TranscriptsDisabled
+      | Error::NoTranscriptFound
+      | Error::LiveStreamUpcoming { .. }
+      | Error::LiveStreamInProgress
+      | Error::CaptionsNotYetAvailable => 4,
+      Error::NotTranslatable | Error::TranslationLanguageNotAvailable => 5,
+      Error::CaptchaRequired | Error::CookiesInvalid | Error::FailedToCreateConsentCookie => 6,
+      Error::NetworkError(_) => 7,
+      Error::InvalidJson(_)
+      | Error::InvalidXml(_)
+      | Error::InvalidDocument(_)
+      | Error::UndefinedInHead(_)
+      | Error::InvalidColor(_)
+      | Error::InvalidTimestamp(_)
+      | Error::UnsupportedFormat(_) => 8,
+    }
+  }
+
+  fn message(&self) -> String {
+    match self {
+      Failure::Captions(err) => err.to_string(),
+      Failure::File(err) => err.to_string(),
+      Failure::UnknownFormat => "the file is neither srv1, srv2 nor srv3".into(),
+    }
+  }
+}
+
+fn parse_lang(lang: &str) -> Result<LanguageTag, String> {
+  LanguageTag::parse(lang).map_err(|err| err.to_string())
+}
+
+fn parse(document: &str, format: Format, strip_annotations: bool) -> Result<Cues, Failure> {
+  let mut cues = Cues::parse(document, format)?;
+  if strip_annotations {
+    cues.strip_annotations();
+  }
+  Ok(cues)
+}
+
+fn export(cues: &Cues, target: Target) -> String {
+  match target {
+    Target::Srt => export::to_srt(cues),
+    Target::Vtt => export::to_vtt(cues),
+    Target::Ass => export::to_ass(cues),
+    Target::Txt => export::to_txt(cues),
+  }
+}
+
+async fn list(json: bool, url: &str) -> Result<(), Failure> {
+  let video_id: VideoId = url.parse()?;
+  let digest = DigestScraper::new(reqwest::Client::new()).fetch(video_id, None).await?;
+
+  if json {
+    let tracks: Vec<_> = digest
+      .captions
+      .iter()
+      .map(|it| {
+        json!({
+          "lang": it.lang_tag.as_str(),
+          "name": it.lang_name,
+          "is_generated": it.is_generated,
+          "is_translatable": it.is_translatable,
+        })
+      })
+      .collect();
+    let mut translations: Vec<_> = digest.can_be_translated_to.iter().collect();
+    translations.sort();
+    println!("{}", json!({ "tracks": tracks, "can_be_translated_to": translations }));
+    return Ok(());
+  }
+
+  for it in &digest.captions {
+    let kind = if it.is_generated { "generated" } else { "manual" };
+    let translatable = if it.is_translatable { "translatable" } else { "" };
+    println!("{}\t{}\t{}\t{}", it.lang_tag, kind, translatable, it.lang_name);
+  }
+  Ok(())
+}
+
+async fn fetch(
+  json: bool,
+  url: &str,
+  lang: LanguageTag,
+  translate: Option<LanguageTag>,
+  format: FetchFormat,
+  strip_annotations: bool,
+) -> Result<(), Failure> {
+  let video_id: VideoId = url.parse()?;
+  let digest = DigestScraper::new(reqwest::Client::new()).fetch(&video_id, None).await?;
+  let mut track = TrackPolicy::PreferManual(lang).select(&digest)?;
+  if let Some(language) = &translate {
+    track.translate_to(language)?;
+  }
+
+  let content = match format.served() {
+    Ok(served @ Format::SRV3) if matches!(format, FetchFormat::Md | FetchFormat::Html) => {
+      let cues = parse(&track.fetch(served).await?, served, strip_annotations)?;
+      let options = TextOptions { chapters: digest.chapters, ..Default::default() };
+      let render = if matches!(format, FetchFormat::Md) { export::to_markdown } else { export::to_html };
+      render(&cues, &video_id, Some(&digest.title), &options)
+    }
+    Ok(format) => track.fetch(format).await?,
+    Err(target) => export(&parse(&track.fetch(Format::SRV1).await?, Format::SRV1, strip_annotations)?, target),
+  };
+
+  if json {
+    let translated_to = translate.as_ref().map(LanguageTag::as_str);
+    let format = format.to_possible_value().map(|it| it.get_name().to_owned());
+    println!(
+      "{}",
+      json!({ "video_id": video_id, "translated_to": translated_to, "format": format, "content": content })
+    );
+  } else {
+    print!("{content}");
+  }
+  Ok(())
+}
+
+async fn search(
+  json: bool,
+  url: &str,
+  query: String,
+  lang: LanguageTag,
+  regex: bool,
+  case_sensitive: bool,
+) -> Result<(), Failure> {
+  let video_id: VideoId = url.parse()?;
+  let digest = DigestScraper::new(reqwest::Client::new()).fetch(&video_id, None).await?;
+  let track = TrackPolicy::PreferManual(lang).select(&digest)?;
+
+  // srv3 has word-level timing
+  let cues = Cues::parse(&track.fetch(Format::SRV3).await?, Format::SRV3)?;
+  let search = if regex { Search::regex(query) } else { Search::phrase(query) };
+  let hits = cues.search(&search.with_case_sensitive(case_sensitive))?;
+
+  if json {
+    println!("{}", json!({ "video_id": video_id, "hits": hits }));
+    return Ok(());
+  }
+  for it in hits {
+    let secs = it.start_millis / 1000;
+    println!(
+      "{:02}:{:02}\thttps://youtu.be/{video_id}?t={secs}\t{} [{}] {}",
+      secs / 60,
+      secs % 60,
+      it.before,
+      it.text,
+      it.after
+    );
+  }
+  Ok(())
+}
+
+async fn dual(url: &str, lang: LanguageTag, second: LanguageTag, format: DualFormat) -> Result<(), Failure> {
+  let video_id: VideoId = url.parse()?;
+  let digest = DigestScraper::new(reqwest::Client::new()).fetch(&video_id, None).await?;
+  let primary = TrackPolicy::PreferManual(lang).select(&digest)?;
+  let secondary = TrackPolicy::TranslateTo(second).select(&digest)?;
+
+  let (primary, secondary) = tokio::try_join!(primary.fetch(Format::SRV1), secondary.fetch(Format::SRV1))?;
+  let pairs = align(&Cues::parse(&primary, Format::SRV1)?, &Cues::parse(&secondary, Format::SRV1)?);
+  let content = match format {
+    DualFormat::Srt => export::to_dual_srt(&pairs),
+    DualFormat::Ass => export::to_dual_ass(&pairs),
+    DualFormat::Json => export::to_dual_json(&pairs),
+  };
+  print!("{content}");
+  Ok(())
+}
+
+fn convert(json: bool, file: PathBuf, to: Target, strip_annotations: bool) -> Result<(), Failure> {
+  let document = std::fs::read_to_string(&file).map_err(Failure::File)?;
+  let format = Format::detect(&document).ok_or(Failure::UnknownFormat)?;
+  let content = export(&parse(&document, format, strip_annotations)?, to);
+
+  if json {
+    let to = to.to_possible_value().map(|it| it.get_name().to_owned());
+    println!("{}", json!({ "from": <&str>::from(format), "to": to, "content": content }));
+  } else {
+    print!("{content}");
+  }
+  Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let result = match cli.command {
+    Command::List { url } => list(cli.json, &url).await,
+    Command::Fetch { url, lang, translate, format, strip_annotations } => {
+      fetch(cli.json, &url, lang, translate, format, strip_annotations).await
+    }
+    Command::Search { url, query, lang, regex, case_sensitive } => {
+      search(cli.json, &url, query, lang, regex, case_sensitive).await
+    }
+    Command::Dual { url, lang, second, format } => {
+      dual(&url, lang, second, if cli.json { DualFormat::Json } else { format }).await
+    }
+    Command::Convert { file, to, strip_annotations } => convert(cli.json, file, to, strip_annotations),
+  };
+
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(failure) => {
+      let code = failure.exit_code();
+      if cli.json {
+        eprintln!("{}", json!({ "error": failure.message(), "exit_code": code }));
+      } else {
+        eprintln!("error: {}", failure.message());
+      }
+      ExitCode::from(code)
+    }
+  }
+}
@@ -0,0 +1,277 @@
+use std::str::FromStr;
+
+use futures::{Stream, StreamExt, TryStreamExt};
+use regex::Regex;
+use reqwest::Url;
+use serde_json::{json, Value};
+
+use crate::error::{Error, Result};
+use crate::scraper::DigestScraper;
+use crate::video_id::VideoId;
+
+/// A playlist, or a channel whose uploads are enumerated.
+///
+/// ```
+/// use youtube_captions::playlist::PlaylistSource;
+///
+/// let course: PlaylistSource = "https://www.youtube.com/playlist?list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs".parse().unwrap();
+/// assert_eq!(course, PlaylistSource::Playlist("PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs".into()));
+///
+/// let channel: PlaylistSource = "https://www.youtube.com/@jetbrainstv/videos".parse().unwrap();
+/// assert_eq!(channel, PlaylistSource::Channel("@jetbrainstv".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PlaylistSource {
+  /// The `list` parameter of a playlist url.
+  Playlist(String),
+  /// A path to the channel page: `channel/UC...`, `@handle`, `c/name` or `user/name`.
+  Channel(String),
+}
+
+/// A video listed in a playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+  pub video_id: VideoId,
+  pub title: String,
+}
+
+/// Everything needed to request the next page.
+struct Continuation {
+  token: String,
+  api_key: String,
+  client_version: String,
+}
+
+impl DigestScraper {
+  /// Lists the videos of a playlist or all the uploads of a channel, requesting more pages as the stream is polled.
+  ///
+  /// ```no_run
+  /// # async fn run() -> youtube_captions::Result<()> {
+  /// use futures::TryStreamExt;
+  /// use youtube_captions::DigestScraper;
+  ///
+  /// let scraper = DigestScraper::new(reqwest::Client::new());
+  /// let videos: Vec<_> = scraper.playlist("https://www.youtube.com/@jetbrainstv".parse()?).try_collect().await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn playlist(&self, source: PlaylistSource) -> impl Stream<Item = Result<PlaylistEntry>> + '_ {
+    // `Some(None)` stands for the first page, `None` means there are no more pages
+    let pages = futures::stream::try_unfold(Some(None), move |state| {
+      let source = source.clone();
+      async move {
+        let (entries, next) = match state {
+          None => return Ok(None),
+          Some(None) => self.fetch_first_page(&source).await?,
+          Some(Some(continuation)) => self.fetch_next_page(continuation).await?,
+        };
+        Ok::<_, Error>(Some((entries, next.map(Some))))
+      }
+    });
+
+    pages.map_ok(|entries: Vec<_>| futures::stream::iter(entries).map(Ok)).try_flatten()
+  }
+
+  async fn fetch_first_page(&self, source: &PlaylistSource) -> Result<(Vec<PlaylistEntry>, Option<Continuation>)> {
+    let list = match source {
+      PlaylistSource::Playlist(list) => list.clone(),
+      PlaylistSource::Channel(path) => self.fetch_uploads_playlist(path).await?,
+    };
+
+    let html = self.fetch_page(playlist_url(&list).as_str()).await?;
+    let data: Value = match Self::extract_json(&html, "var ytInitialData") {
+      Some(data) => data?,
+      None if html.contains(r#"class="g-recaptcha""#) => return Err(Error::CaptchaRequired),
+      None => return Err(Error::PlaylistUnavailable),
+    };
+
+    let api_key = Self::extract_config(&html, "INNERTUBE_API_KEY");
+    let client_version = Self::extract_config(&html, "INNERTUBE_CLIENT_VERSION");
+    let (entries, token) = collect_page(&data);
+    let next = token.zip(api_key.zip(client_version));
+    let next = next.map(|(token, (api_key, client_version))| Continuation { token, api_key, client_version });
+    Ok((entries, next))
+  }
+
+  async fn fetch_next_page(&self, continuation: Continuation) -> Result<(Vec<PlaylistEntry>, Option<Continuation>)> {
+    let url = format!("https://www.youtube.com/youtubei/v1/browse?key={}", continuation.api_key);
+    let body = json!({
+      "context": { "client": { "clientName": "WEB", "clientVersion": continuation.client_version, "hl": "en" } },
+      "continuation": continuation.token,
+    });
+
+    let (entries, token) = collect_page(&self.post_json(&url, &body).await?);
+    Ok((entries, token.map(|token| Continuation { token, ..continuation })))
+  }
+
+  /// Every channel `UC...` has an auto-generated playlist `UU...` with all of its uploads.
+  async fn fetch_uploads_playlist(&self, path: &str) -> Result<String> {
+    lazy_static::lazy_static! {
+      static ref RE: Regex = Regex::new(r#""externalId":"UC([\w-]{22})""#).unwrap();
+    }
+
+    if let Some(id) = path.strip_prefix("channel/UC") {
+      return Ok(format!("UU{id}"));
+    }
+
+    let html = self.fetch_page(channel_url(path).as_str()).await?;
+    RE.captures(&html).map(|caps| format!("UU{}", &caps[1])).ok_or(Error::PlaylistUnavailable)
+  }
+
+  fn extract_config(html: &str, key: &str) -> Option<String> {
+    Self::extract_json(html, &format!(r#""{key}":"#)).and_then(Result::ok)
+  }
+}
+
+/// The list comes from the user, so it is encoded rather than pasted into the query.
+fn playlist_url(list: &str) -> Url {
+  let params = [("list", list), ("hl", "en")];
+  Url::parse_with_params("https://www.youtube.com/playlist", params).expect("the base url is valid")
+}
+
+/// Every part of the path is encoded, so a handle can't add a query or climb up with `..`.
+fn channel_url(path: &str) -> Url {
+  let mut url = Url::parse("https://www.youtube.com/").expect("the base url is valid");
+  url.path_segments_mut().expect("the base url has a path").extend(path.split('/'));
+  url.query_pairs_mut().append_pair("hl", "en");
+  url
+}
+
+/// Walks the whole response, as the renderers are nested differently on the page and in continuations.
+fn collect_page(data: &Value) -> (Vec<PlaylistEntry>, Option<String>) {
+  fn walk(value: &Value, entries: &mut Vec<PlaylistEntry>, token: &mut Option<String>) {
+    match value {
+      Value::Array(items) => items.iter().for_each(|it| walk(it, entries, token)),
+      Value::Object(fields) => {
+        if let Some(video) = fields.get("playlistVideoRenderer") {
+          entries.extend(entry(video));
+        } else if let Some(Value::String(next)) = fields
+          .get("continuationItemRenderer")
+          .and_then(|it| it.pointer("/continuationEndpoint/continuationCommand/token"))
+        {
+          token.get_or_insert_with(|| next.clone());
+        } else {
+          fields.values().for_each(|it| walk(it, entries, token));
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn entry(video: &Value) -> Option<PlaylistEntry> {
+    let video_id = video.get("videoId")?.as_str()?.parse().ok()?;
+    let title = video.get("title")?;
+    let title = match title.get("simpleText") {
+      Some(text) => text.as_str()?.to_owned(),
+      None => title.get("runs")?.as_array()?.iter().filter_map(|it| it.get("text")?.as_str()).collect(),
+    };
+    Some(PlaylistEntry { video_id, title })
+  }
+
+  let (mut entries, mut token) = (vec![], None);
+  walk(data, &mut entries, &mut token);
+  (entries, token)
+}
+
+impl FromStr for PlaylistSource {
+  type Err = Error;
+
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    let input = input.trim();
+    let is_id = |id: &str| id.len() >= 12 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+
+    if input.starts_with('@') && !input.contains('/') {
+      return Ok(Self::Channel(input.to_owned()));
+    }
+    if input.starts_with("UC") && input.len() == 24 && is_id(input) {
+      return Ok(Self::Channel(format!("channel/{input}")));
+    }
+    if is_id(input) {
+      return Ok(Self::Playlist(input.to_owned()));
+    }
+
+    let url = if input.contains("://") { Url::parse(input) } else { Url::parse(&format!("https://{input}")) };
+    let url = url.map_err(|_| Error::InvalidPlaylist(input.to_owned()))?;
+    let host = url.host_str().unwrap_or_default().trim_start_matches("www.");
+    if !matches!(host, "youtube.com" | "m.youtube.com" | "music.youtube.com") {
+      return Err(Error::InvalidPlaylist(input.to_owned()));
+    }
+
+    if let Some((_, list)) = url.query_pairs().find(|(key, _)| key == "list") {
+      return Ok(Self::Playlist(list.into_owned()));
+    }
+
+    let mut path = url.path_segments().into_iter().flatten().filter(|it| !it.is_empty());
+    match (path.next(), path.next()) {
+      (Some(handle), _) if handle.starts_with('@') => Ok(Self::Channel(handle.to_owned())),
+      (Some(kind @ ("channel" | "c" | "user")), Some(name)) => Ok(Self::Channel(format!("{kind}/{name}"))),
+      _ => Err(Error::InvalidPlaylist(input.to_owned())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sources_are_parsed() {
+    let sources = [
+      ("PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs", PlaylistSource::Playlist("PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs".into())),
+      (
+        "https://www.youtube.com/watch?v=JRMOIE_wAFk&list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs&index=2",
+        PlaylistSource::Playlist("PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs".into()),
+      ),
+      ("https://music.youtube.com/playlist?list=OLAK5uy_k", PlaylistSource::Playlist("OLAK5uy_k".into())),
+      ("@jetbrainstv", PlaylistSource::Channel("@jetbrainstv".into())),
+      ("youtube.com/@jetbrainstv/videos", PlaylistSource::Channel("@jetbrainstv".into())),
+      ("UCGp4UBwpTNegd_4nCpuBcow", PlaylistSource::Channel("channel/UCGp4UBwpTNegd_4nCpuBcow".into())),
+      (
+        "https://www.youtube.com/channel/UCGp4UBwpTNegd_4nCpuBcow/videos",
+        PlaylistSource::Channel("channel/UCGp4UBwpTNegd_4nCpuBcow".into()),
+      ),
+      ("https://www.youtube.com/c/JetBrainsTV", PlaylistSource::Channel("c/JetBrainsTV".into())),
+      ("https://www.youtube.com/user/JetBrainsTV", PlaylistSource::Channel("user/JetBrainsTV".into())),
+    ];
+
+    for (input, expected) in sources {
+      assert_eq!(input.parse::<PlaylistSource>().unwrap(), expected, "{input}");
+    }
+
+    for input in ["", "https://youtu.be/JRMOIE_wAFk", "https://example.com/playlist?list=PL590L5WQmH8"] {
+      assert!(input.parse::<PlaylistSource>().is_err(), "{input}");
+    }
+  }
+
+  #[test]
+  fn lists_are_encoded_in_urls() {
+    let url = playlist_url("PL5&hl=de#x");
+    assert_eq!(url.as_str(), "https://www.youtube.com/playlist?list=PL5%26hl%3Dde%23x&hl=en");
+    assert_eq!(url.query_pairs().next().unwrap().1, "PL5&hl=de#x");
+  }
+
+  #[test]
+  fn channels_are_encoded_in_urls() {
+    assert_eq!(channel_url("c/JetBrainsTV").as_str(), "https://www.youtube.com/c/JetBrainsTV?hl=en");
+    assert_eq!(channel_url("@a?hl=de#x").as_str(), "https://www.youtube.com/@a%3Fhl=de%23x?hl=en");
+    assert_eq!(channel_url("@a/../watch").as_str(), "https://www.youtube.com/@a/watch?hl=en");
+  }
+
+  #[test]
+  fn videos_and_continuation_are_collected() {
+    let html = r#"<script>var ytInitialData = {"contents": {"playlistVideoListRenderer": {"contents": [
+      {"playlistVideoRenderer": {"videoId": "JRMOIE_wAFk", "title": {"runs": [{"text": "Kotlin "}, {"text": "Coroutines"}]}}},
+      {"playlistVideoRenderer": {"videoId": "PHzOOQfhPFg", "title": {"simpleText": "Just A Girl"}}},
+      {"playlistVideoRenderer": {"videoId": "broken", "title": {"simpleText": "[Deleted video]"}}},
+      {"continuationItemRenderer": {"continuationEndpoint": {"continuationCommand": {"token": "4qmFsgKBARIk"}}}}
+    ]}}};</script>"#;
+
+    let data: Value = DigestScraper::extract_json(html, "var ytInitialData").unwrap().unwrap();
+    let (entries, token) = collect_page(&data);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].video_id.as_str(), "JRMOIE_wAFk");
+    assert_eq!(entries[0].title, "Kotlin Coroutines");
+    assert_eq!(entries[1].title, "Just A Girl");
+    assert_eq!(token.as_deref(), Some("4qmFsgKBARIk"));
+  }
+}
@@ -1,27 +1,43 @@
 use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use language_tags::LanguageTag;
 use regex::Regex;
 use serde::Deserialize;
 
+use crate::cache::{self, Cache};
+use crate::cue::Chapter;
 use crate::error::{Error, Result};
 use crate::format::*;
+use crate::video_id::VideoId;
 
 pub struct DigestScraper {
   cookie: tokio::sync::RwLock<Option<String>>,
   http: reqwest::Client,
+  cache: Option<Arc<Cache>>,
 }
 
 #[derive(Debug)]
 pub struct Digest {
+  pub video_id: VideoId,
+  pub title: String,
+  /// Chapters listed in the description, see [`Chapter::from_description`].
+  pub chapters: Vec<Chapter>,
   pub captions: Vec<CaptionScraper>,
   pub can_be_translated_to: HashSet<String>,
+  /// `true` for finished live streams. Their generated captions appear some time after the end,
+  /// see [`DigestScraper::wait_for_captions`].
+  pub is_live_content: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct CaptionScraper {
   pub(crate) url: String,
   pub(crate) http: reqwest::Client,
+  pub(crate) cache: Option<Arc<Cache>>,
+  pub(crate) video_id: VideoId,
+  pub(crate) translated_to: Option<LanguageTag>,
   /// `true`, if generated using automatic speech recognition
   pub is_generated: bool,
   /// `true`, if can be translated with [`CaptionScraper::translate_to`] method.
@@ -32,7 +48,13 @@ pub struct CaptionScraper {
 
 impl DigestScraper {
   pub fn new(http: reqwest::Client) -> Self {
-    Self { http, cookie: tokio::sync::RwLock::new(None) }
+    Self { http, cookie: tokio::sync::RwLock::new(None), cache: None }
+  }
+
+  /// Serves watch pages and caption tracks from the cache, see [`Cache`] for details.
+  pub fn with_cache(mut self, cache: Cache) -> Self {
+    self.cache = Some(Arc::new(cache));
+    self
   }
 
   async fn get(&self, url: &str) -> Result<String> {
@@ -42,16 +64,26 @@ impl DigestScraper {
     Ok(res.error_for_status()?.text().await?)
   }
 
-  async fn fetch_video_page(&self, video_id: &str, lang: &str) -> Result<String> {
-    let url = format!(r#"https://youtube.com/watch?hl={}&persist_hl=1&v={}"#, lang, video_id);
-    let mut html = self.get(&url).await?;
+  /// Calls the internal API used by the YouTube web client.
+  pub(crate) async fn post_json(&self, url: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+    let cookie = self.cookie.read().await;
+    let cookie = cookie.as_ref().map_or("", std::ops::Deref::deref);
+    let req = self.http.post(url).header("Cookie", cookie).header("Content-Type", "application/json");
+    let res = req.body(body.to_string()).send().await?;
+    Ok(serde_json::from_str(&res.error_for_status()?.text().await?)?)
+  }
+
+  /// Fetches a YouTube page, giving consent to saving cookies, if YouTube asks for it.
+  pub(crate) async fn fetch_page(&self, url: &str) -> Result<String> {
+    let mut html = self.get(url).await?;
 
     let consent = r#"action="https://consent.youtube.com/s""#;
     if html.contains(consent) {
       let mut cookie = self.cookie.write().await;
       *cookie = Some(Self::extract_consent_cookie(&html)?);
+      drop(cookie);
 
-      html = self.get(&url).await?;
+      html = self.get(url).await?;
       if html.contains(consent) {
         return Err(Error::FailedToCreateConsentCookie);
       }
@@ -60,6 +92,11 @@ impl DigestScraper {
     Ok(html)
   }
 
+  async fn fetch_video_page(&self, video_id: &VideoId, lang: &str) -> Result<String> {
+    let url = format!(r#"https://youtube.com/watch?hl={}&persist_hl=1&v={}"#, lang, video_id);
+    self.fetch_page(&url).await
+  }
+
   fn extract_consent_cookie(html: &str) -> Result<String, Error> {
     lazy_static::lazy_static! {
       static ref RE: Regex = Regex::new(r#"name="v" value="(.*?)"#).unwrap();
@@ -69,27 +106,70 @@ impl DigestScraper {
       .map(|caps| format!("CONSENT=YES+{};Domain=.youtube.com", &caps[1]))
   }
 
-  fn extract_captions_json(html: &str) -> Result<RawDigest> {
+  /// Parses a JSON object assigned to a variable in an inline script, like `var ytInitialData = {...};`.
+  pub(crate) fn extract_json<'de, T: Deserialize<'de>>(html: &'de str, marker: &str) -> Option<Result<T>> {
+    let (_, json) = html.split_once(marker)?;
+    let json = json.trim_start().trim_start_matches('=').trim_start();
+    let value = serde_json::Deserializer::from_str(json).into_iter().next()?;
+    Some(value.map_err(Error::from))
+  }
+
+  fn extract_captions_json(html: &str) -> Result<(RawDigest, RawVideoDetails)> {
+    let details: RawVideoDetails =
+      Self::extract_json(html, r#""videoDetails":"#).and_then(Result::ok).unwrap_or_default();
+
+    if details.is_upcoming {
+      let scheduled_start = Self::extract_json::<String>(html, r#""scheduledStartTime":"#)
+        .and_then(Result::ok)
+        .and_then(|secs| secs.parse().ok())
+        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+      return Err(Error::LiveStreamUpcoming { scheduled_start });
+    }
+    if details.is_live {
+      return Err(Error::LiveStreamInProgress);
+    }
+
     let (_, html) = html.split_once(r#""captions":"#).ok_or_else(|| match html {
       html if html.contains(r#"class="g-recaptcha""#) => Error::CaptchaRequired,
       html if !html.contains(r#""playabilityStatus":"#) => Error::VideoUnavailable,
+      _ if details.is_live_content => Error::CaptionsNotYetAvailable,
       _ => Error::TranscriptsDisabled,
     })?;
 
     let (json, _) = html.split_once(r#","videoDetails"#).ok_or(Error::TranscriptsDisabled)?;
     let data: RawData = serde_json::from_str(json)?;
-    Ok(data.captions)
+    Ok((data.captions, details))
   }
 
-  pub async fn fetch<'a, Str: Into<Option<&'a str>>>(&self, video_id: &str, lang: Str) -> Result<Digest> {
+  pub async fn fetch<'a, Str: Into<Option<&'a str>>>(&self, video_id: impl Into<VideoId>, lang: Str) -> Result<Digest> {
+    let video_id = video_id.into();
     let lang = lang.into().unwrap_or("en");
-    let html = self.fetch_video_page(video_id, lang).await?;
-    let digest = DigestScraper::extract_captions_json(&html)?;
+
+    let key = cache::Key { video_id: video_id.as_str(), lang, is_generated: false, translate_to: None, format: None };
+    let cached = match &self.cache {
+      Some(cache) => cache.fresh(&key).await,
+      None => None,
+    };
+
+    let is_cached = cached.is_some();
+    let html = match cached {
+      Some(html) => html,
+      None => self.fetch_video_page(&video_id, lang).await?,
+    };
+
+    // only pages with captions are cached, so that polling a live stream does not hit the cache
+    let (digest, details) = DigestScraper::extract_captions_json(&html)?;
+    if let (Some(cache), false) = (&self.cache, is_cached) {
+      cache.store(&key, &html).await;
+    }
 
     let convert = |it: RawCaptionTrack| {
       CaptionScraper {
         url: it.base_url,
         http: self.http.clone(),
+        cache: self.cache.clone(),
+        video_id: video_id.clone(),
+        translated_to: None,
         lang_name: it.name.text,
         is_generated: matches!(it.kind.as_deref(), Some("asr")),
         is_translatable: it.is_translatable,
@@ -98,8 +178,12 @@ impl DigestScraper {
     };
 
     Ok(Digest {
+      video_id: video_id.clone(),
+      title: details.title,
+      chapters: Chapter::from_description(&details.short_description),
       captions: digest.caption_tracks.into_iter().map(convert).collect(),
       can_be_translated_to: digest.translation_languages.into_iter().map(|it| it.code).collect(),
+      is_live_content: details.is_live_content,
     })
   }
 }
@@ -113,6 +197,7 @@ impl CaptionScraper {
     if self.is_translatable {
       self.url.push_str("&tlang=");
       self.url.push_str(language.as_str());
+      self.translated_to = Some(language.clone());
       Ok(self)
     } else {
       Err(Error::NotTranslatable)
@@ -122,9 +207,49 @@ impl CaptionScraper {
   /// The parameter specifies that the caption track should be returned in a specific format.
   /// If the parameter is not included in the request, the track is returned in its original format.
   pub async fn fetch(&self, format: Format) -> Result<String> {
-    let format: &str = format.into();
-    let url = format!("{}&fmt={}", &self.url, format);
-    Ok(self.http.get(&url).send().await?.text().await?)
+    let url = format!("{}&fmt={}", &self.url, <&str>::from(format));
+    let Some(cache) = &self.cache else {
+      return Ok(self.http.get(&url).send().await?.error_for_status()?.text().await?);
+    };
+
+    let key = cache::Key {
+      video_id: self.video_id.as_str(),
+      lang: self.lang_tag.as_str(),
+      is_generated: self.is_generated,
+      translate_to: self.translated_to.as_ref().map(LanguageTag::as_str),
+      format: Some(format),
+    };
+    cache.fetch(&key, self.http.get(&url)).await
+  }
+
+  /// Parses the cues while the track is still being downloaded, so the first ones of a long track
+  /// are available right away. The cache is not used. Only srv1, srv2 and srv3 are supported.
+  ///
+  /// ```no_run
+  /// # async fn run(track: youtube_captions::CaptionScraper) -> youtube_captions::Result<()> {
+  /// use futures::TryStreamExt;
+  /// use youtube_captions::format::Format;
+  ///
+  /// let mut cues = std::pin::pin!(track.stream(Format::SRV3));
+  /// while let Some(cue) = cues.try_next().await? {
+  ///   println!("{}", cue.text);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+  pub fn stream(&self, format: Format) -> impl futures::Stream<Item = Result<crate::cue::Cue>> + '_ {
+    use crate::stream::{ensure_supported, CueReader};
+    use futures::TryStreamExt;
+
+    let reader = async move {
+      ensure_supported(format)?;
+      let url = format!("{}&fmt={}", &self.url, <&str>::from(format));
+      let res = self.http.get(&url).send().await?.error_for_status()?;
+      let body = res.bytes_stream().map_err(std::io::Error::other);
+      CueReader::new(tokio_util::io::StreamReader::new(body), format)
+    };
+    futures::stream::once(reader).map_ok(CueReader::into_stream).try_flatten()
   }
 
   #[cfg(feature = "json3")]
@@ -132,18 +257,22 @@ impl CaptionScraper {
     unimplemented!()
   }
 
+  /// The texts are copied out of the fetched document. Parse [`Self::fetch`] with [`srv1::Transcript::parse`]
+  /// to borrow them instead.
   #[cfg(feature = "srv1")]
-  pub async fn fetch_srv1(&self) -> Result<srv1::Transcript> {
+  pub async fn fetch_srv1(&self) -> Result<srv1::Transcript<'static>> {
     Ok(self.fetch(Format::SRV1).await?.parse()?)
   }
 
+  /// See [`Self::fetch_srv1`].
   #[cfg(feature = "srv2")]
-  pub async fn fetch_srv2(&self) -> Result<srv2::Transcript> {
+  pub async fn fetch_srv2(&self) -> Result<srv2::Transcript<'static>> {
     Ok(self.fetch(Format::SRV2).await?.parse()?)
   }
 
+  /// See [`Self::fetch_srv1`].
   #[cfg(feature = "srv3")]
-  pub async fn fetch_srv3(&self) -> Result<srv3::Transcript> {
+  pub async fn fetch_srv3(&self) -> Result<srv3::Transcript<'static>> {
     Ok(self.fetch(Format::SRV3).await?.parse()?)
   }
 
@@ -178,6 +307,21 @@ struct RawCaptionTrack {
   pub name: RawName,
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawVideoDetails {
+  #[serde(default)]
+  pub title: String,
+  #[serde(default)]
+  pub short_description: String,
+  #[serde(default)]
+  pub is_live: bool,
+  #[serde(default)]
+  pub is_upcoming: bool,
+  #[serde(default)]
+  pub is_live_content: bool,
+}
+
 #[derive(Debug, Deserialize)]
 struct RawLanguage {
   #[serde(rename = "languageCode")]
@@ -191,3 +335,54 @@ struct RawName {
   #[serde(rename = "simpleText")]
   pub text: String,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn page(player_response: &str) -> String {
+    format!(r#"<html><script>var ytInitialPlayerResponse = {player_response};</script></html>"#)
+  }
+
+  #[test]
+  fn upcoming_streams_report_the_scheduled_start() {
+    let html = page(
+      r#"{"playabilityStatus":{"status":"LIVE_STREAM_OFFLINE","liveStreamability":{"liveStreamabilityRenderer":{
+        "offlineSlate":{"liveStreamOfflineSlateRenderer":{"scheduledStartTime":"1700000000"}}}}},
+        "videoDetails":{"videoId":"JRMOIE_wAFk","isLiveContent":true,"isUpcoming":true}}"#,
+    );
+
+    let err = DigestScraper::extract_captions_json(&html).unwrap_err();
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    assert!(matches!(err, Error::LiveStreamUpcoming { scheduled_start: Some(it) } if it == start));
+  }
+
+  #[test]
+  fn live_streams_are_detected() {
+    let html = page(r#"{"playabilityStatus":{"status":"OK"},"videoDetails":{"isLive":true,"isLiveContent":true}}"#);
+    let err = DigestScraper::extract_captions_json(&html).unwrap_err();
+    assert!(matches!(err, Error::LiveStreamInProgress));
+
+    let html = page(r#"{"playabilityStatus":{"status":"OK"},"videoDetails":{"isLiveContent":true}}"#);
+    let err = DigestScraper::extract_captions_json(&html).unwrap_err();
+    assert!(matches!(err, Error::CaptionsNotYetAvailable));
+
+    let html = page(r#"{"playabilityStatus":{"status":"OK"},"videoDetails":{"isLiveContent":false}}"#);
+    let err = DigestScraper::extract_captions_json(&html).unwrap_err();
+    assert!(matches!(err, Error::TranscriptsDisabled));
+  }
+
+  #[test]
+  fn finished_streams_with_captions_are_fetched() {
+    let html = page(
+      r#"{"playabilityStatus":{"status":"OK"},"captions":{"playerCaptionsTracklistRenderer":{
+        "captionTracks":[{"baseUrl":"https://www.youtube.com/api/timedtext?v=JRMOIE_wAFk","name":{"simpleText":"English (auto-generated)"},
+        "languageCode":"en","kind":"asr","isTranslatable":true}],"translationLanguages":[]}},"videoDetails":{
+        "videoId":"JRMOIE_wAFk","isLiveContent":true}}"#,
+    );
+
+    let (digest, details) = DigestScraper::extract_captions_json(&html).unwrap();
+    assert_eq!(digest.caption_tracks.len(), 1);
+    assert!(details.is_live_content);
+  }
+}
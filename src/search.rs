@@ -0,0 +1,179 @@
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::cue::Cues;
+use crate::error::Result;
+
+/// A query for [`Cues::search`]. Case and diacritics are ignored by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+  pattern: Pattern,
+  case_sensitive: bool,
+  diacritic_sensitive: bool,
+  context_words: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern {
+  Phrase(String),
+  Regex(String),
+}
+
+/// A match with the timing of its words.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hit {
+  pub start_millis: u32,
+  pub end_millis: u32,
+  /// The whole words containing the match.
+  pub text: String,
+  /// Words said right before the match.
+  pub before: String,
+  /// Words said right after the match.
+  pub after: String,
+}
+
+impl Search {
+  pub const DEFAULT_CONTEXT_WORDS: usize = 8;
+
+  /// Whole words, possibly spanning several cues. Whitespaces match any line breaks and cue boundaries.
+  pub fn phrase(text: impl Into<String>) -> Self {
+    Self::new(Pattern::Phrase(text.into()))
+  }
+
+  /// A regular expression matched against the words of the transcript joined with single spaces.
+  pub fn regex(pattern: impl Into<String>) -> Self {
+    Self::new(Pattern::Regex(pattern.into()))
+  }
+
+  fn new(pattern: Pattern) -> Self {
+    Self { pattern, case_sensitive: false, diacritic_sensitive: false, context_words: Self::DEFAULT_CONTEXT_WORDS }
+  }
+
+  pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+    self.case_sensitive = case_sensitive;
+    self
+  }
+
+  /// When `false`, `resume` matches `résumé`.
+  pub fn with_diacritic_sensitive(mut self, diacritic_sensitive: bool) -> Self {
+    self.diacritic_sensitive = diacritic_sensitive;
+    self
+  }
+
+  /// The number of words in [`Hit::before`] and [`Hit::after`].
+  pub fn with_context(mut self, words: usize) -> Self {
+    self.context_words = words;
+    self
+  }
+
+  fn fold(&self, text: &str) -> String {
+    if self.diacritic_sensitive {
+      text.to_owned()
+    } else {
+      text.nfd().filter(|it| !is_combining_mark(*it)).nfc().collect()
+    }
+  }
+}
+
+impl Cues {
+  /// Finds where the query is said. Timing is word-level for formats with spans, like srv3, and cue-level otherwise.
+  pub fn search(&self, search: &Search) -> Result<Vec<Hit>> {
+    let pattern = match &search.pattern {
+      Pattern::Regex(pattern) => search.fold(pattern),
+      Pattern::Phrase(text) => {
+        let text = search.fold(text);
+        let words: Vec<_> = text.split_whitespace().map(regex::escape).collect();
+        let boundary = |c: Option<char>| if c.is_some_and(char::is_alphanumeric) { r"\b" } else { "" };
+        let (first, last) = (text.trim().chars().next(), text.trim().chars().last());
+        format!("{}{}{}", boundary(first), words.join(r"\s+"), boundary(last))
+      }
+    };
+    let regex = RegexBuilder::new(&pattern).case_insensitive(!search.case_sensitive).build()?;
+
+    let words = self.words();
+    let texts: Vec<_> = words.iter().map(|it| it.text()).collect();
+    let mut haystack = String::new();
+    let mut starts = Vec::with_capacity(words.len());
+    for text in &texts {
+      if !haystack.is_empty() {
+        haystack.push(' ');
+      }
+      starts.push(haystack.len());
+      haystack.push_str(&search.fold(text));
+    }
+
+    let join = |range: &[std::borrow::Cow<str>]| range.join(" ");
+    let hits = regex.find_iter(&haystack).filter(|it| !it.is_empty()).map(|it| {
+      let first = starts.partition_point(|&start| start <= it.start()) - 1;
+      let last = starts.partition_point(|&start| start < it.end()) - 1;
+      Hit {
+        start_millis: words[first].start_millis,
+        end_millis: words[last].end_millis,
+        text: join(&texts[first..=last]),
+        before: join(&texts[first.saturating_sub(search.context_words)..first]),
+        after: join(&texts[last + 1..(last + 1 + search.context_words).min(texts.len())]),
+      }
+    });
+    Ok(hits.collect())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::{Cue, Span};
+
+  fn cues() -> Cues {
+    vec![
+      Cue::new(0, 2000, "We're going to talk about Kotlin"),
+      Cue::new(2000, 2000, "Coroutines and their Résumé.\nThen about"),
+      Cue::with_spans(
+        4000,
+        3000,
+        vec![Span::new(0, "structured", Default::default()), Span::new(600, " concurrency", Default::default())],
+      ),
+    ]
+    .into()
+  }
+
+  #[test]
+  fn phrases_span_cues() {
+    let hits = cues().search(&Search::phrase("kotlin  coroutines").with_context(2)).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].start_millis, hits[0].end_millis), (0, 4000));
+    assert_eq!(hits[0].text, "Kotlin Coroutines");
+    assert_eq!((hits[0].before.as_str(), hits[0].after.as_str()), ("talk about", "and their"));
+  }
+
+  #[test]
+  fn case_and_diacritics_are_ignored() {
+    assert_eq!(cues().search(&Search::phrase("resume")).unwrap()[0].text, "Résumé.");
+    assert!(cues().search(&Search::phrase("resume").with_diacritic_sensitive(true)).unwrap().is_empty());
+    assert!(cues().search(&Search::phrase("kotlin").with_case_sensitive(true)).unwrap().is_empty());
+    assert!(cues().search(&Search::phrase("rout")).unwrap().is_empty());
+  }
+
+  #[test]
+  fn spans_give_word_timing() {
+    let hits = cues().search(&Search::regex(r"con\w+")).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].start_millis, hits[0].end_millis), (4600, 7000));
+
+    let hits = cues().search(&Search::regex(r"about \w+")).unwrap();
+    let texts: Vec<_> = hits.iter().map(|it| (it.text.as_str(), it.start_millis, it.end_millis)).collect();
+    assert_eq!(texts, [("about Kotlin", 0, 2000), ("about structured", 2000, 4600)]);
+
+    assert!(cues().search(&Search::regex("(")).is_err());
+  }
+
+  #[test]
+  fn readings_are_not_searched() {
+    let spans =
+      vec![Span::new(0, "漢字", Default::default()).with_ruby("かんじ"), Span::new(500, "を読む", Default::default())];
+    let cues: Cues = vec![Cue::with_spans(0, 1000, spans)].into();
+    assert!(cues.search(&Search::phrase("かんじ")).unwrap().is_empty());
+    assert_eq!(cues.search(&Search::regex("漢字を")).unwrap()[0].text, "漢字を読む");
+  }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use tokio::io::AsyncBufRead;
+
+use crate::cue::Cue;
+use crate::error::{Error, Result};
+#[cfg(feature = "srv1")]
+use crate::format::srv1;
+#[cfg(feature = "srv2")]
+use crate::format::srv2;
+#[cfg(feature = "srv3")]
+use crate::format::srv3;
+use crate::format::{self, Format};
+
+/// Reads cues one by one while the document is still being received, see [`crate::CaptionScraper::stream`].
+///
+/// Every caption element is cut out of the document and parsed on its own, so the cues are the same
+/// as the ones of [`crate::cue::Cues::parse`]. Empty cues are skipped.
+///
+/// ```
+/// # async fn run() -> youtube_captions::Result<()> {
+/// use youtube_captions::format::Format;
+/// use youtube_captions::stream::CueReader;
+///
+/// let xml = r#"<transcript><text start="0" dur="1.5">Hello</text></transcript>"#;
+/// let mut reader = CueReader::new(xml.as_bytes(), Format::SRV1)?;
+/// while let Some(cue) = reader.next_cue().await? {
+///   println!("{}", cue.text);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CueReader<R> {
+  reader: Reader<R>,
+  format: Format,
+  buf: Vec<u8>,
+  /// Names of the elements opened so far.
+  path: Vec<Vec<u8>>,
+  /// Pens and windows read so far.
+  #[cfg(feature = "srv3")]
+  head: srv3::Head,
+}
+
+impl<R: AsyncBufRead + Unpin> CueReader<R> {
+  /// Fails with [`Error::UnsupportedFormat`], unless the format is srv1, srv2 or srv3.
+  /// json3 is not streamed yet, as there is no json3 parser to cut the events out of the document.
+  pub fn new(reader: R, format: Format) -> Result<Self> {
+    ensure_supported(format)?;
+    Ok(Self {
+      reader: Reader::from_reader(reader),
+      format,
+      buf: vec![],
+      path: vec![],
+      #[cfg(feature = "srv3")]
+      head: Default::default(),
+    })
+  }
+
+  /// Reads the document until the next cue, `None` at the end of it.
+  pub async fn next_cue(&mut self) -> Result<Option<Cue>> {
+    while let Some((name, xml)) = self.next_element().await? {
+      let cue = match (self.format, name.as_str()) {
+        #[cfg(feature = "srv1")]
+        (Format::SRV1, _) => {
+          let mut seg: srv1::TextSegment = quick_xml::de::from_str(&xml)?;
+          format::unescape(&mut seg.value).map_err(escape_error)?;
+          Cue::from(seg)
+        }
+        #[cfg(feature = "srv2")]
+        (Format::SRV2, _) => {
+          let mut seg: srv2::TextSegment = quick_xml::de::from_str(&xml)?;
+          format::unescape(&mut seg.value).map_err(escape_error)?;
+          Cue::from(seg)
+        }
+        #[cfg(feature = "srv3")]
+        (Format::SRV3, "p") => {
+          let mut seg: srv3::TextSegment = quick_xml::de::from_str(&xml)?;
+          for text in &mut seg.value {
+            format::unescape(text).map_err(escape_error)?;
+          }
+          Cue::from_srv3(&seg, &self.head)
+        }
+        #[cfg(feature = "srv3")]
+        (Format::SRV3, "pen") => {
+          self.head.pens.push(quick_xml::de::from_str(&xml)?);
+          continue;
+        }
+        #[cfg(feature = "srv3")]
+        (Format::SRV3, "ws") => {
+          self.head.window_styling.push(quick_xml::de::from_str(&xml)?);
+          continue;
+        }
+        #[cfg(feature = "srv3")]
+        (Format::SRV3, _) => {
+          self.head.window_positioning.push(quick_xml::de::from_str(&xml)?);
+          continue;
+        }
+        _ => return Err(Error::UnsupportedFormat(self.format)),
+      };
+
+      if !cue.text.trim().is_empty() {
+        return Ok(Some(cue));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Turns the reader into a stream, which ends after the first error.
+  pub fn into_stream(self) -> impl futures::Stream<Item = Result<Cue>> {
+    futures::stream::try_unfold(self, |mut reader| async move { Ok(reader.next_cue().await?.map(|it| (it, reader))) })
+  }
+
+  /// The next element holding a cue, a pen or a window, with its name and its markup.
+  async fn next_element(&mut self) -> Result<Option<(String, String)>> {
+    loop {
+      self.buf.clear();
+      let event = self.reader.read_event_into_async(&mut self.buf).await.map_err(error)?;
+      let (start, is_empty) = match event {
+        Event::Start(start) => (start, false),
+        Event::Empty(start) => (start, true),
+        Event::End(_) => {
+          self.path.pop();
+          continue;
+        }
+        Event::Eof => return Ok(None),
+        _ => continue,
+      };
+
+      let name = start.name().as_ref().to_owned();
+      if !is_wanted(self.format, &self.path, &name) {
+        if !is_empty {
+          self.path.push(name);
+        }
+        continue;
+      }
+
+      let name = self.reader.decoder().decode(&name).map_err(error)?.into_owned();
+      let mut xml = format!("<{}", self.reader.decoder().decode(&start).map_err(error)?);
+      if is_empty {
+        xml.push_str("/>");
+        return Ok(Some((name, xml)));
+      }
+      xml.push('>');
+
+      let mut depth = 0;
+      loop {
+        self.buf.clear();
+        let decoder = self.reader.decoder();
+        let event = self.reader.read_event_into_async(&mut self.buf).await.map_err(error)?;
+        let decode = |bytes: &[u8]| decoder.decode(bytes).map(|it| it.into_owned()).map_err(error);
+        match event {
+          Event::Start(it) => {
+            depth += 1;
+            xml.push_str(&format!("<{}>", decode(&it)?));
+          }
+          Event::Empty(it) => xml.push_str(&format!("<{}/>", decode(&it)?)),
+          Event::End(it) => {
+            xml.push_str(&format!("</{}>", decode(&it)?));
+            if depth == 0 {
+              return Ok(Some((name, xml)));
+            }
+            depth -= 1;
+          }
+          Event::Text(it) => xml.push_str(&decode(&it)?),
+          Event::CData(it) => xml.push_str(&format!("<![CDATA[{}]]>", decode(&it)?)),
+          Event::Eof => return Err(error(quick_xml::Error::UnexpectedEof(name))),
+          _ => {}
+        }
+      }
+    }
+  }
+}
+
+pub(crate) fn ensure_supported(format: Format) -> Result<()> {
+  match format {
+    #[cfg(feature = "srv1")]
+    Format::SRV1 => Ok(()),
+    #[cfg(feature = "srv2")]
+    Format::SRV2 => Ok(()),
+    #[cfg(feature = "srv3")]
+    Format::SRV3 => Ok(()),
+    _ => Err(Error::UnsupportedFormat(format)),
+  }
+}
+
+/// Texts of srv1 and srv2, as well as pens, windows and paragraphs of srv3.
+fn is_wanted(format: Format, path: &[Vec<u8>], name: &[u8]) -> bool {
+  match (format, path) {
+    (Format::SRV1 | Format::SRV2, [_root]) => name == b"text",
+    (Format::SRV3, [_root, parent]) if parent == b"head" => matches!(name, b"pen" | b"ws" | b"wp"),
+    (Format::SRV3, [_root, parent]) if parent == b"body" => name == b"p",
+    _ => false,
+  }
+}
+
+/// Failures of a response body are network errors, the rest are invalid documents.
+fn error(err: quick_xml::Error) -> Error {
+  let quick_xml::Error::Io(io) = err else { return Error::InvalidXml(err.into()) };
+  match Arc::try_unwrap(io) {
+    Ok(io) if io.get_ref().is_some_and(|it| it.is::<reqwest::Error>()) => {
+      Error::NetworkError(*io.into_inner().and_then(|it| it.downcast().ok()).expect("checked above"))
+    }
+    Ok(io) => Error::InvalidXml(quick_xml::Error::Io(io.into()).into()),
+    Err(io) => Error::InvalidXml(quick_xml::Error::Io(io).into()),
+  }
+}
+
+fn escape_error(err: quick_xml::escape::EscapeError) -> Error {
+  Error::InvalidXml(quick_xml::Error::EscapeError(err).into())
+}
+
+#[cfg(test)]
+mod tests {
+  use futures::TryStreamExt;
+  use tokio::io::BufReader;
+
+  use super::*;
+  use crate::cue::Cues;
+
+  /// A tiny buffer, so the elements are split between reads.
+  async fn streamed(xml: &str, format: Format) -> Result<Cues> {
+    let reader = CueReader::new(BufReader::with_capacity(7, xml.as_bytes()), format)?;
+    Ok(reader.into_stream().try_collect::<Vec<_>>().await?.into())
+  }
+
+  #[cfg(feature = "srv1")]
+  #[tokio::test]
+  async fn srv1_is_streamed() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><transcript>
+      <text start="4.333" dur="2.1">it&amp;#39;s a &lt;b&gt;test&lt;/b&gt;</text>
+      <text start="6.5" dur="1">second</text>
+      <text start="8" dur="1"><![CDATA[raw <text>]]></text>
+      <text start="9" dur="1"> </text>
+    </transcript>"#;
+    assert_eq!(streamed(xml, Format::SRV1).await.unwrap(), Cues::parse(xml, Format::SRV1).unwrap());
+  }
+
+  #[cfg(feature = "srv3")]
+  #[tokio::test]
+  async fn srv3_is_streamed() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" b="1"/><pen id="2" rb="1"/><pen id="3" rb="2"/><ws id="0"/><wp id="0"/></head>
+      <body>
+        <w id="1" t="0" wp="0" ws="0"/>
+        <p t="0" d="1000" wp="0" ws="0"><s p="1">Take</s><s t="500"> this</s></p>
+        <p t="1000" d="1000"><s p="2">漢字</s><s p="3">かんじ</s></p>
+        <p t="2000" d="1000">plain &amp;amp; simple</p>
+      </body>
+    </timedtext>"#;
+    let cues = streamed(xml, Format::SRV3).await.unwrap();
+    assert_eq!(cues, Cues::parse(xml, Format::SRV3).unwrap());
+    assert_eq!(cues.len(), 3);
+  }
+
+  #[cfg(feature = "srv2")]
+  #[tokio::test]
+  async fn cues_are_read_before_the_end() {
+    use tokio::io::AsyncWriteExt;
+
+    let (mut tx, rx) = tokio::io::duplex(1024);
+    let mut reader = CueReader::new(BufReader::new(rx), Format::SRV2).unwrap();
+
+    tx.write_all(br#"<timedtext><window t="0" id="1" op="define"/><text t="0" d="500">one</text><te"#).await.unwrap();
+    assert_eq!(reader.next_cue().await.unwrap().map(|it| it.text), Some("one".into()));
+
+    tx.write_all(br#"xt t="500" d="500">two</text></timedtext>"#).await.unwrap();
+    drop(tx);
+    assert_eq!(reader.next_cue().await.unwrap().map(|it| it.start_millis), Some(500));
+    assert_eq!(reader.next_cue().await.unwrap(), None);
+
+    let xml =
+      r#"<timedtext><text t="0" d="500">one</text><text t="500" d="500" append="1">it&amp;#39;s</text></timedtext>"#;
+    assert_eq!(streamed(xml, Format::SRV2).await.unwrap(), Cues::parse(xml, Format::SRV2).unwrap());
+  }
+
+  #[tokio::test]
+  async fn unsupported_formats_are_rejected() {
+    assert!(matches!(CueReader::new(&b""[..], Format::JSON3), Err(Error::UnsupportedFormat(Format::JSON3))));
+  }
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// A moment of a video with millisecond precision, up to about 49 days.
+///
+/// Parsed from SRT, WebVTT and TTML clocks, as well as TTML offsets:
+///
+/// ```
+/// use youtube_captions::time::{Duration, Timestamp};
+///
+/// let time: Timestamp = "01:02:05,005".parse().unwrap();
+/// assert_eq!(time, "1:02:05.005".parse().unwrap());
+/// assert_eq!(time + Duration::from_millis(995), "3726s".parse().unwrap());
+/// assert_eq!(time.to_vtt(), "01:02:05.005");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(u32);
+
+/// A time span with millisecond precision. Subtractions saturate at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Duration(u32);
+
+impl Timestamp {
+  pub const ZERO: Timestamp = Timestamp(0);
+
+  pub const fn from_millis(millis: u32) -> Self {
+    Self(millis)
+  }
+
+  pub const fn as_millis(self) -> u32 {
+    self.0
+  }
+
+  /// Decimal seconds, like `4.333`, parsed without going through floats.
+  pub fn from_secs_str(secs: &str) -> Result<Self> {
+    decimal(secs, 1000).map(Self).ok_or_else(|| Error::InvalidTimestamp(secs.to_owned()))
+  }
+
+  /// SubRip, `HH:MM:SS,mmm`.
+  pub fn to_srt(self) -> String {
+    self.clock(',')
+  }
+
+  /// WebVTT, `HH:MM:SS.mmm`.
+  pub fn to_vtt(self) -> String {
+    self.clock('.')
+  }
+
+  /// TTML clock time, `HH:MM:SS.mmm`.
+  pub fn to_ttml(self) -> String {
+    self.clock('.')
+  }
+
+  /// Advanced SubStation Alpha, `H:MM:SS.cc`.
+  pub fn to_ass(self) -> String {
+    let (secs, centis) = (self.0 / 1000, self.0 % 1000 / 10);
+    format!("{}:{:02}:{:02}.{centis:02}", secs / 3600, secs / 60 % 60, secs % 60)
+  }
+
+  fn clock(self, separator: char) -> String {
+    let (secs, millis) = (self.0 / 1000, self.0 % 1000);
+    format!("{:02}:{:02}:{:02}{separator}{millis:03}", secs / 3600, secs / 60 % 60, secs % 60)
+  }
+
+  #[cfg(feature = "srv1")]
+  pub(crate) fn deserialize_secs<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct Secs;
+
+    impl serde::de::Visitor<'_> for Secs {
+      type Value = Timestamp;
+
+      fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("decimal seconds")
+      }
+
+      fn visit_str<E: serde::de::Error>(self, secs: &str) -> Result<Self::Value, E> {
+        Timestamp::from_secs_str(secs).map_err(E::custom)
+      }
+    }
+
+    // a visitor, so the attribute is not copied into a string
+    deserializer.deserialize_str(Secs)
+  }
+}
+
+impl Duration {
+  pub const ZERO: Duration = Duration(0);
+
+  pub const fn from_millis(millis: u32) -> Self {
+    Self(millis)
+  }
+
+  pub const fn as_millis(self) -> u32 {
+    self.0
+  }
+
+  pub const fn is_zero(self) -> bool {
+    self.0 == 0
+  }
+
+  /// Decimal seconds, like `2.1`, parsed without going through floats.
+  pub fn from_secs_str(secs: &str) -> Result<Self> {
+    Timestamp::from_secs_str(secs).map(|it| Self(it.0))
+  }
+
+  #[cfg(feature = "srv1")]
+  pub(crate) fn deserialize_secs<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Timestamp::deserialize_secs(deserializer).map(|it| Self(it.0))
+  }
+}
+
+/// Parses `123.4567` into units of `1 / scale`, rounding the extra digits.
+fn decimal(input: &str, scale: u32) -> Option<u32> {
+  let (int, fraction) = input.split_once('.').unwrap_or((input, ""));
+  let is_digits = |it: &str| it.bytes().all(|b| b.is_ascii_digit());
+  if int.is_empty() || !is_digits(int) || !is_digits(fraction) || input.ends_with('.') {
+    return None;
+  }
+
+  let digits = scale.ilog10() as usize;
+  // too many digits fail already in the parsing, and every step after it is checked
+  let mut units = int.parse::<u64>().ok()?.checked_mul(scale as u64)?;
+  for (i, digit) in fraction.bytes().map(|b| (b - b'0') as u64).enumerate().take(digits + 1) {
+    match i {
+      _ if i < digits => units = units.checked_add(digit * 10u64.pow((digits - i - 1) as u32))?,
+      _ if digit >= 5 => units = units.checked_add(1)?,
+      _ => {}
+    }
+  }
+  u32::try_from(units).ok()
+}
+
+impl FromStr for Timestamp {
+  type Err = Error;
+
+  /// `[HH:]MM:SS[.,]mmm` clocks, or TTML offsets like `4.5s`, `4500ms`, `1.25h`.
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    let invalid = || Error::InvalidTimestamp(input.to_owned());
+    let input = input.trim();
+
+    let millis = if input.contains(':') {
+      let (clock, fraction) = input.rsplit_once(['.', ',']).unwrap_or((input, "0"));
+      let field = |it: &str, max_len| {
+        let is_valid = !it.is_empty() && it.len() <= max_len && it.bytes().all(|b| b.is_ascii_digit());
+        is_valid.then(|| it.parse::<u64>().ok()).flatten()
+      };
+      let (hours, minutes, secs) = match clock.split(':').collect::<Vec<_>>().as_slice() {
+        [m, s] => (Some(0), field(m, 2), field(s, 2)),
+        [h, m, s] => (field(h, 4), field(m, 2), field(s, 2)),
+        _ => return Err(invalid()),
+      };
+      let (Some(hours), Some(minutes @ 0..60), Some(secs @ 0..60)) = (hours, minutes, secs) else {
+        return Err(invalid());
+      };
+      let fraction = decimal(&format!("0.{fraction}"), 1000).ok_or_else(invalid)?;
+      ((hours * 60 + minutes) * 60 + secs) * 1000 + fraction as u64
+    } else {
+      let (number, scale) = match input {
+        _ if input.ends_with("ms") => (&input[..input.len() - 2], 1),
+        _ if input.ends_with('s') => (&input[..input.len() - 1], 1000),
+        _ if input.ends_with('m') => (&input[..input.len() - 1], 60_000),
+        _ if input.ends_with('h') => (&input[..input.len() - 1], 3_600_000),
+        _ => return Err(invalid()),
+      };
+      // thousandths of the unit
+      let units = decimal(number, 1000).ok_or_else(invalid)?;
+      (units as u64 * scale + 500) / 1000
+    };
+    u32::try_from(millis).map(Self).map_err(|_| invalid())
+  }
+}
+
+impl fmt::Display for Timestamp {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.to_vtt())
+  }
+}
+
+impl Add<Duration> for Timestamp {
+  type Output = Timestamp;
+
+  fn add(self, rhs: Duration) -> Self::Output {
+    Self(self.0.saturating_add(rhs.0))
+  }
+}
+
+impl AddAssign<Duration> for Timestamp {
+  fn add_assign(&mut self, rhs: Duration) {
+    *self = *self + rhs;
+  }
+}
+
+impl Sub<Duration> for Timestamp {
+  type Output = Timestamp;
+
+  fn sub(self, rhs: Duration) -> Self::Output {
+    Self(self.0.saturating_sub(rhs.0))
+  }
+}
+
+impl SubAssign<Duration> for Timestamp {
+  fn sub_assign(&mut self, rhs: Duration) {
+    *self = *self - rhs;
+  }
+}
+
+impl Sub for Timestamp {
+  type Output = Duration;
+
+  fn sub(self, rhs: Timestamp) -> Self::Output {
+    Duration(self.0.saturating_sub(rhs.0))
+  }
+}
+
+impl Add for Duration {
+  type Output = Duration;
+
+  fn add(self, rhs: Duration) -> Self::Output {
+    Self(self.0.saturating_add(rhs.0))
+  }
+}
+
+impl Sub for Duration {
+  type Output = Duration;
+
+  fn sub(self, rhs: Duration) -> Self::Output {
+    Self(self.0.saturating_sub(rhs.0))
+  }
+}
+
+impl From<Duration> for std::time::Duration {
+  fn from(value: Duration) -> Self {
+    std::time::Duration::from_millis(value.0 as u64)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clocks_are_parsed() {
+    let millis = |it: &str| it.parse::<Timestamp>().map(Timestamp::as_millis).ok();
+    assert_eq!(millis("01:02:05,005"), Some(3_725_005));
+    assert_eq!(millis("02:05.5"), Some(125_500));
+    assert_eq!(millis("100:00:00.000"), Some(360_000_000));
+    assert_eq!(millis("00:00:01.0006"), Some(1001));
+    assert_eq!(millis("1.5s"), Some(1500));
+    assert_eq!(millis("250ms"), Some(250));
+    assert_eq!(millis("0.5h"), Some(1_800_000));
+    let invalid = [
+      "",
+      "1",
+      "00:60:00.000",
+      "00:00:61",
+      "1:2:3:4",
+      "x:00",
+      "00:00:01.x",
+      "-1s",
+      "1500:00:00.000",
+      "18446744073709552s",
+      "18446744073709551616ms",
+    ];
+    for invalid in invalid {
+      assert_eq!(millis(invalid), None, "{invalid}");
+    }
+  }
+
+  #[test]
+  fn secs_are_exact() {
+    assert_eq!(Timestamp::from_secs_str("4.333").unwrap().as_millis(), 4333);
+    assert_eq!(Timestamp::from_secs_str("16777.217").unwrap().as_millis(), 16_777_217);
+    assert_eq!(Duration::from_secs_str("2.1").unwrap().as_millis(), 2100);
+    assert!(Duration::from_secs_str("2.").is_err());
+    assert!(Timestamp::from_secs_str("18446744073709552").is_err());
+    assert!(Timestamp::from_secs_str("18446744073709551.9999").is_err());
+  }
+
+  #[test]
+  fn clocks_are_formatted() {
+    let time = Timestamp::from_millis(3_725_005);
+    assert_eq!(
+      (time.to_srt(), time.to_vtt(), time.to_ass()),
+      ("01:02:05,005".into(), "01:02:05.005".into(), "1:02:05.00".into())
+    );
+    assert_eq!(time - Timestamp::from_millis(3_725_000), Duration::from_millis(5));
+    assert_eq!(Timestamp::ZERO - Duration::from_millis(5), Timestamp::ZERO);
+  }
+}
@@ -0,0 +1,340 @@
+use std::ops::Range;
+
+use crate::cue::{Cue, Cues};
+#[cfg(feature = "srv3")]
+use crate::format::srv3;
+#[cfg(feature = "srv3")]
+use crate::time::{Duration, Timestamp};
+#[cfg(feature = "srv3")]
+use std::collections::{BTreeSet, HashMap};
+
+fn shifted(millis: u32, offset_millis: i64) -> u32 {
+  (millis as i64 + offset_millis).clamp(0, u32::MAX as i64) as u32
+}
+
+fn scaled(millis: u32, ratio: f64) -> u32 {
+  (millis as f64 * ratio).round().clamp(0.0, u32::MAX as f64) as u32
+}
+
+/// Trims `start..start + duration` to the window. Returns how far the start moved, `None` if it is outside the window.
+fn clip(start: &mut u32, duration: &mut u32, window: &Range<u32>) -> Option<u32> {
+  let end = start.saturating_add(*duration);
+  let overlaps = *start < window.end && end > window.start;
+  let is_instant_inside = *duration == 0 && window.contains(start);
+  if !overlaps && !is_instant_inside {
+    return None;
+  }
+
+  let cut = window.start.saturating_sub(*start);
+  *start = (*start).max(window.start);
+  *duration = end.min(window.end) - *start;
+  Some(cut)
+}
+
+impl Cues {
+  /// Moves the cues in time. Cues moved before zero are trimmed or removed.
+  pub fn shift(&mut self, offset_millis: i64) {
+    if offset_millis < 0 {
+      self.clip(offset_millis.unsigned_abs().min(u32::MAX as u64) as u32..u32::MAX);
+    }
+    for it in self.iter_mut() {
+      // the end is shifted too, so a cue moved to `u32::MAX` doesn't outlast it
+      let end = shifted(it.end_millis(), offset_millis);
+      it.start_millis = shifted(it.start_millis, offset_millis);
+      it.duration_millis = end - it.start_millis;
+    }
+  }
+
+  /// Multiplies all times by the ratio, e.g. `23.976 / 25.0` to convert from NTSC film to PAL frame rate.
+  pub fn scale(&mut self, ratio: f64) {
+    for it in self.iter_mut() {
+      let end = scaled(it.end_millis(), ratio);
+      it.start_millis = scaled(it.start_millis, ratio);
+      it.duration_millis = end - it.start_millis;
+      for span in &mut it.spans {
+        span.offset_millis = scaled(span.offset_millis, ratio);
+      }
+    }
+  }
+
+  /// Keeps only what is shown inside the window. Cues overlapping its edges are trimmed,
+  /// dropping the spans that would start after the end. The times are not rebased, see [`Cues::shift`].
+  pub fn clip(&mut self, window: Range<u32>) {
+    self.retain_mut(|it| it.clip(&window));
+  }
+
+  /// Adds the cues of another transcript, moved by the offset, e.g. the start of the next clip.
+  pub fn append(&mut self, mut other: Cues, offset_millis: u32) {
+    other.shift(offset_millis as i64);
+    let mut cues = std::mem::take(self).into_inner();
+    cues.extend(other);
+    *self = cues.into();
+  }
+}
+
+impl Cue {
+  fn clip(&mut self, window: &Range<u32>) -> bool {
+    let Some(cut) = clip(&mut self.start_millis, &mut self.duration_millis, window) else {
+      return false;
+    };
+
+    if !self.spans.is_empty() {
+      let duration = self.duration_millis;
+      for it in &mut self.spans {
+        it.offset_millis = it.offset_millis.saturating_sub(cut);
+      }
+      self.spans.retain(|it| it.offset_millis == 0 || it.offset_millis < duration);
+      self.text = self.spans.iter().map(|it| it.text.as_str()).collect();
+    }
+    true
+  }
+}
+
+#[cfg(feature = "srv3")]
+impl<'a> srv3::Transcript<'a> {
+  /// Moves the segments and the windows in time. Segments moved before zero are trimmed or removed.
+  pub fn shift(&mut self, offset_millis: i64) {
+    if offset_millis < 0 {
+      self.clip(offset_millis.unsigned_abs().min(u32::MAX as u64) as u32..u32::MAX);
+    }
+    for it in &mut self.body.elements {
+      match it {
+        srv3::Element::Segment(it) => {
+          let end = Timestamp::from_millis(shifted(it.end().as_millis(), offset_millis));
+          it.start = Timestamp::from_millis(shifted(it.start.as_millis(), offset_millis));
+          it.duration = end - it.start;
+        }
+        srv3::Element::Window(it) => it.start = Timestamp::from_millis(shifted(it.start.as_millis(), offset_millis)),
+      }
+    }
+  }
+
+  /// Multiplies all times by the ratio, including the relative times of spans.
+  pub fn scale(&mut self, ratio: f64) {
+    for it in &mut self.body.elements {
+      match it {
+        srv3::Element::Segment(it) => {
+          let end = Timestamp::from_millis(scaled(it.end().as_millis(), ratio));
+          it.start = Timestamp::from_millis(scaled(it.start.as_millis(), ratio));
+          it.duration = end - it.start;
+          for it in &mut it.value {
+            if let srv3::Text::Span(it) = it {
+              it.offset = Duration::from_millis(scaled(it.offset.as_millis(), ratio));
+            }
+          }
+        }
+        srv3::Element::Window(it) => it.start = Timestamp::from_millis(scaled(it.start.as_millis(), ratio)),
+      }
+    }
+  }
+
+  /// Keeps only what is shown inside the window, like [`Cues::clip`].
+  /// Windows defined before the window are moved to its start, so the kept segments still have them.
+  pub fn clip(&mut self, window: Range<u32>) {
+    self.body.elements.retain_mut(|it| match it {
+      srv3::Element::Window(it) => {
+        it.start = it.start.max(Timestamp::from_millis(window.start));
+        it.start.as_millis() < window.end
+      }
+      srv3::Element::Segment(seg) => {
+        let (mut start, mut duration) = (seg.start.as_millis(), seg.duration.as_millis());
+        let Some(cut) = clip(&mut start, &mut duration, &window) else {
+          return false;
+        };
+        (seg.start, seg.duration) = (Timestamp::from_millis(start), Duration::from_millis(duration));
+        seg.value.retain_mut(|it| match it {
+          srv3::Text::Span(it) => {
+            it.offset = it.offset - Duration::from_millis(cut);
+            it.offset.is_zero() || it.offset.as_millis() < duration
+          }
+          srv3::Text::Str(_) => true,
+        });
+        true
+      }
+    });
+  }
+
+  /// Adds the segments and the windows of another transcript, moved by the offset.
+  /// Its pens, window styles and positions get new ids, so they don't clash with the existing ones.
+  pub fn append(&mut self, mut other: srv3::Transcript<'a>, offset_millis: u32) {
+    other.shift(offset_millis as i64);
+
+    let mut pens = Renumbered::new(self.head.pens.iter().map(|it| it.id));
+    let mut styles = Renumbered::new(self.head.window_styling.iter().map(|it| it.id));
+    let mut positions = Renumbered::new(self.head.window_positioning.iter().map(|it| it.id));
+    let mut windows = Renumbered::new(self.body.elements.iter().filter_map(|it| match it {
+      srv3::Element::Window(it) => Some(it.id),
+      srv3::Element::Segment(_) => None,
+    }));
+
+    for it in &mut other.head.pens {
+      it.id = pens.id(it.id);
+    }
+    for it in &mut other.head.window_styling {
+      it.id = styles.id(it.id);
+    }
+    for it in &mut other.head.window_positioning {
+      it.id = positions.id(it.id);
+    }
+    for it in &mut other.body.elements {
+      match it {
+        srv3::Element::Segment(it) => {
+          it.pen_id = it.pen_id.map(|id| pens.id(id));
+          it.window_style_id = it.window_style_id.map(|id| styles.id(id));
+          it.window_position_id = it.window_position_id.map(|id| positions.id(id));
+          for it in &mut it.value {
+            if let srv3::Text::Span(it) = it {
+              it.pen_id = it.pen_id.map(|id| pens.id(id));
+            }
+          }
+        }
+        srv3::Element::Window(it) => {
+          it.id = windows.id(it.id);
+          it.window_style_id = styles.id(it.window_style_id);
+          it.window_position_id = positions.id(it.window_position_id);
+        }
+      }
+    }
+
+    self.head.pens.append(&mut other.head.pens);
+    self.head.window_styling.append(&mut other.head.window_styling);
+    self.head.window_positioning.append(&mut other.head.window_positioning);
+    self.body.elements.append(&mut other.body.elements);
+    self.body.elements.sort_by_key(|it| match it {
+      srv3::Element::Segment(it) => it.start,
+      srv3::Element::Window(it) => it.start,
+    });
+  }
+}
+
+/// New ids for the appended transcript: free ones after the largest taken id, wrapping around to the smallest.
+/// References to undefined ids get new ids too, so they don't point at the existing definitions.
+#[cfg(feature = "srv3")]
+struct Renumbered {
+  taken: BTreeSet<u32>,
+  ids: HashMap<u32, u32>,
+  next: u32,
+}
+
+#[cfg(feature = "srv3")]
+impl Renumbered {
+  fn new(taken: impl Iterator<Item = u32>) -> Self {
+    let taken: BTreeSet<_> = taken.collect();
+    let next = taken.last().map_or(0, |it| it.wrapping_add(1));
+    Self { taken, ids: HashMap::new(), next }
+  }
+
+  fn id(&mut self, id: u32) -> u32 {
+    if let Some(it) = self.ids.get(&id) {
+      return *it;
+    }
+    while self.taken.contains(&self.next) {
+      self.next = self.next.wrapping_add(1);
+    }
+    self.taken.insert(self.next);
+    self.ids.insert(id, self.next);
+    self.next
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::Span;
+
+  fn cues() -> Cues {
+    let spans = vec![Span::new(0, "one", Default::default()), Span::new(1500, " two", Default::default())];
+    vec![Cue::new(0, 1000, "intro"), Cue::with_spans(1000, 2000, spans), Cue::new(4000, 1000, "outro")].into()
+  }
+
+  fn timing(cues: &Cues) -> Vec<(u32, u32, &str)> {
+    cues.iter().map(|it| (it.start_millis, it.end_millis(), it.text.as_str())).collect()
+  }
+
+  #[test]
+  fn cues_are_shifted_and_scaled() {
+    let mut cues = cues();
+    cues.shift(-500);
+    assert_eq!(timing(&cues), [(0, 500, "intro"), (500, 2500, "one two"), (3500, 4500, "outro")]);
+    assert_eq!(cues[1].spans[1].offset_millis, 1500);
+
+    cues.scale(2.0);
+    assert_eq!(timing(&cues), [(0, 1000, "intro"), (1000, 5000, "one two"), (7000, 9000, "outro")]);
+    assert_eq!(cues[1].spans[1].offset_millis, 3000);
+  }
+
+  #[test]
+  fn cues_are_clipped() {
+    let mut clip = cues();
+    clip.clip(1200..2400);
+    assert_eq!(timing(&clip), [(1200, 2400, "one")]);
+
+    let mut clip = cues();
+    clip.clip(2600..4500);
+    assert_eq!(timing(&clip), [(2600, 3000, "one two"), (4000, 4500, "outro")]);
+    assert_eq!(clip[0].spans[1].offset_millis, 0);
+  }
+
+  #[test]
+  fn cues_are_appended() {
+    let mut cues = cues();
+    cues.append(vec![Cue::new(0, 1000, "next")].into(), 5000);
+    assert_eq!(timing(&cues).last(), Some(&(5000, 6000, "next")));
+  }
+
+  #[test]
+  fn cues_shifted_past_the_end_are_clamped() {
+    let mut cues = cues();
+    cues.shift(u32::MAX as i64 - 2000);
+    let last = &cues[2];
+    assert_eq!((last.start_millis, last.duration_millis), (u32::MAX, 0));
+    assert_eq!((cues[1].start_millis, cues[1].end_millis()), (u32::MAX - 1000, u32::MAX));
+  }
+
+  #[cfg(feature = "srv3")]
+  #[test]
+  fn srv3_windows_and_pens_stay_consistent() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" b="1"/><wp id="0"/><ws id="0"/></head>
+      <body>
+        <w id="1" t="0" wp="0" ws="0"/>
+        <p t="1000" d="2000" wp="0" ws="0"><s p="1">one</s><s t="1500"> two</s></p>
+      </body>
+    </timedtext>"#;
+    let transcript: srv3::Transcript = xml.parse().unwrap();
+
+    let mut clip = transcript.clone();
+    clip.clip(1500..2600);
+    clip.shift(-1500);
+    let srv3::Element::Window(window) = &clip.body.elements[0] else { panic!() };
+    let srv3::Element::Segment(seg) = &clip.body.elements[1] else { panic!() };
+    let millis = (window.start.as_millis(), seg.start.as_millis(), seg.duration.as_millis());
+    assert_eq!(millis, (0, 0, 1100));
+    assert_eq!(seg.value.len(), 2);
+
+    let mut both = transcript.clone();
+    both.append(transcript, 3000);
+    assert_eq!(both.head.pens.iter().map(|it| it.id).collect::<Vec<_>>(), [1, 2]);
+    let srv3::Element::Segment(seg) = &both.body.elements[3] else { panic!() };
+    assert_eq!((seg.start.as_millis(), seg.window_position_id), (4000, Some(1)));
+    let srv3::Text::Span(span) = &seg.value[0] else { panic!() };
+    assert_eq!(span.pen_id, Some(2));
+  }
+
+  #[cfg(feature = "srv3")]
+  #[test]
+  fn srv3_ids_are_renumbered_without_overflow() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="4294967295"/><pen id="0"/></head>
+      <body><p t="0" d="1000" p="4294967295">one</p></body>
+    </timedtext>"#;
+    let transcript: srv3::Transcript = xml.parse().unwrap();
+
+    let mut both = transcript.clone();
+    both.append(transcript, 1000);
+    assert_eq!(both.head.pens.iter().map(|it| it.id).collect::<Vec<_>>(), [u32::MAX, 0, 1, 2]);
+    let srv3::Element::Segment(seg) = &both.body.elements[1] else { panic!() };
+    assert_eq!(seg.pen_id, Some(1));
+    assert!(both.runs().is_ok());
+  }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cue::Cues;
+#[cfg(feature = "srv3")]
+use crate::format::srv3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+  /// Shown anyway, but hard to read.
+  Warning,
+  /// Not shown as intended, or rejected by YouTube.
+  Error,
+}
+
+/// Where the problem is. Spans are ordered right after their elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Location {
+  /// Index in [`Cues`].
+  Cue(usize),
+  /// Index in [`srv3::Body::elements`].
+  Element(usize),
+  /// Index of a span in [`srv3::TextSegment::value`] of the element.
+  Span { element: usize, span: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Problem {
+  /// Shown at the same time as another cue or segment.
+  Overlap {
+    with: Location,
+  },
+  /// Never shown.
+  ZeroDuration,
+  ReadingSpeed {
+    chars_per_sec: f32,
+  },
+  LineTooLong {
+    chars: usize,
+    max: usize,
+  },
+  MissingPen(u32),
+  MissingWindowStyle(u32),
+  MissingWindowPosition(u32),
+  /// A ruby base without its reading, or a reading without its base.
+  UnbalancedRuby,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub location: Location,
+  pub problem: Problem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationOptions {
+  /// Common guidelines allow 17 to 21 characters per second.
+  pub max_chars_per_sec: f32,
+  /// Applies to cues without a window position that sets [`srv3::WindowPosition::columns_total`].
+  pub max_line_chars: Option<usize>,
+}
+
+impl Default for ValidationOptions {
+  fn default() -> Self {
+    Self { max_chars_per_sec: 21.0, max_line_chars: Some(42) }
+  }
+}
+
+/// What the checks need to know about a cue or a segment.
+struct Item<'a> {
+  location: Location,
+  start_millis: u32,
+  duration_millis: u32,
+  text: std::borrow::Cow<'a, str>,
+  max_line_chars: Option<usize>,
+}
+
+impl Cues {
+  /// Checks timing, reading speed and line lengths, ordered by location.
+  pub fn validate(&self, options: &ValidationOptions) -> Vec<Diagnostic> {
+    let items = self.iter().enumerate().map(|(i, it)| Item {
+      location: Location::Cue(i),
+      start_millis: it.start_millis,
+      duration_millis: it.duration_millis,
+      text: it.text.as_str().into(),
+      max_line_chars: options.max_line_chars,
+    });
+    check(items.collect(), options)
+  }
+}
+
+#[cfg(feature = "srv3")]
+impl srv3::Transcript<'_> {
+  /// Checks timing, reading speed and line lengths like [`Cues::validate`], ruby annotations,
+  /// as well as pens and windows referenced by the segments, but missing in the head.
+  pub fn validate(&self, options: &ValidationOptions) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut error = |location, problem| diagnostics.push(Diagnostic { severity: Severity::Error, location, problem });
+
+    let has_pen = |id: &u32| self.head.pens.iter().any(|it| it.id == *id);
+    let has_style = |id: &u32| self.head.window_styling.iter().any(|it| it.id == *id);
+    let position = |id: u32| self.head.window_positioning.iter().find(|it| it.id == id);
+
+    let mut items = vec![];
+    for (i, it) in self.body.elements.iter().enumerate() {
+      let (pen_id, style_id, position_id) = match it {
+        srv3::Element::Segment(it) => (it.pen_id, it.window_style_id, it.window_position_id),
+        srv3::Element::Window(it) => (None, Some(it.window_style_id), Some(it.window_position_id)),
+      };
+      if let Some(id) = pen_id.filter(|it| !has_pen(it)) {
+        error(Location::Element(i), Problem::MissingPen(id));
+      }
+      if let Some(id) = style_id.filter(|it| !has_style(it)) {
+        error(Location::Element(i), Problem::MissingWindowStyle(id));
+      }
+      if let Some(id) = position_id.filter(|it| position(*it).is_none()) {
+        error(Location::Element(i), Problem::MissingWindowPosition(id));
+      }
+
+      let srv3::Element::Segment(seg) = it else { continue };
+      for (j, it) in seg.value.iter().enumerate() {
+        if let srv3::Text::Span(srv3::Span { pen_id: Some(id), .. }) = it {
+          if !has_pen(id) {
+            error(Location::Span { element: i, span: j }, Problem::MissingPen(*id));
+          }
+        }
+      }
+
+      let units = seg.ruby_units(&self.head).unwrap_or_default();
+      if units.iter().any(|it| it.annotation.is_none() && it.base.pen.ruby != srv3::RubyPart::None) {
+        error(Location::Element(i), Problem::UnbalancedRuby);
+      }
+
+      let columns = seg.window_position_id.and_then(position).and_then(|it| it.columns_total);
+      items.push(Item {
+        location: Location::Element(i),
+        start_millis: seg.start.as_millis(),
+        duration_millis: seg.duration.as_millis(),
+        text: seg.text().into(),
+        max_line_chars: columns.map(usize::from).or(options.max_line_chars),
+      });
+    }
+
+    diagnostics.extend(check(items, options));
+    diagnostics.sort_by_key(|it| it.location);
+    diagnostics
+  }
+}
+
+fn check(mut items: Vec<Item>, options: &ValidationOptions) -> Vec<Diagnostic> {
+  let mut diagnostics = vec![];
+  let mut push = |severity, location, problem| diagnostics.push(Diagnostic { severity, location, problem });
+
+  items.retain(|it| !it.text.trim().is_empty());
+  items.sort_by_key(|it| it.start_millis);
+
+  // the item shown the longest so far
+  let mut longest: Option<(Location, u32)> = None;
+  for it in &items {
+    let end_millis = it.start_millis.saturating_add(it.duration_millis);
+    match longest {
+      Some((with, end)) if it.start_millis < end => push(Severity::Warning, it.location, Problem::Overlap { with }),
+      _ => {}
+    }
+    if longest.is_none_or(|(_, end)| end_millis > end) {
+      longest = Some((it.location, end_millis));
+    }
+
+    if it.duration_millis == 0 {
+      push(Severity::Error, it.location, Problem::ZeroDuration);
+    } else {
+      let chars = it.text.chars().filter(|it| !it.is_whitespace()).count();
+      let chars_per_sec = chars as f32 * 1000.0 / it.duration_millis as f32;
+      if chars_per_sec > options.max_chars_per_sec {
+        push(Severity::Warning, it.location, Problem::ReadingSpeed { chars_per_sec });
+      }
+    }
+
+    let Some(max) = it.max_line_chars else { continue };
+    if let Some(chars) = it.text.lines().map(|it| it.trim().chars().count()).filter(|it| *it > max).max() {
+      push(Severity::Warning, it.location, Problem::LineTooLong { chars, max });
+    }
+  }
+
+  diagnostics.sort_by_key(|it| it.location);
+  diagnostics
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Problem::Overlap { with } => write!(f, "overlaps {with}"),
+      Problem::ZeroDuration => write!(f, "the duration is zero"),
+      Problem::ReadingSpeed { chars_per_sec } => write!(f, "{chars_per_sec:.1} characters per second is too fast"),
+      Problem::LineTooLong { chars, max } => write!(f, "a line has {chars} characters, more than {max}"),
+      Problem::MissingPen(id) => write!(f, "pen {id} is not defined"),
+      Problem::MissingWindowStyle(id) => write!(f, "window style {id} is not defined"),
+      Problem::MissingWindowPosition(id) => write!(f, "window position {id} is not defined"),
+      Problem::UnbalancedRuby => write!(f, "a ruby base and its reading don't match"),
+    }
+  }
+}
+
+impl Location {
+  /// Cues come before the elements, so the order matches the equality.
+  fn key(&self) -> (bool, usize, Option<usize>) {
+    match *self {
+      Location::Cue(i) => (false, i, None),
+      Location::Element(i) => (true, i, None),
+      Location::Span { element, span } => (true, element, Some(span)),
+    }
+  }
+}
+
+impl Ord for Location {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    self.key().cmp(&other.key())
+  }
+}
+
+impl PartialOrd for Location {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl fmt::Display for Location {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Location::Cue(i) => write!(f, "cue #{i}"),
+      Location::Element(i) => write!(f, "element #{i}"),
+      Location::Span { element, span } => write!(f, "span #{span} of element #{element}"),
+    }
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{severity}: {}: {}", self.location, self.problem)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cue::Cue;
+
+  #[test]
+  fn cues_are_validated() {
+    let cues: Cues = vec![
+      Cue::new(0, 2000, "Hello everyone!"),
+      Cue::new(1500, 1000, "This cue overlaps the previous one and is read way too fast"),
+      Cue::new(3000, 0, "Never shown"),
+      Cue::new(4000, 5000, "A line that is a bit too long for a default limit"),
+    ]
+    .into();
+
+    let problems: Vec<_> = cues.validate(&Default::default()).into_iter().map(|it| it.to_string()).collect();
+    assert_eq!(
+      problems,
+      [
+        "warning: cue #1: overlaps cue #0",
+        "warning: cue #1: 48.0 characters per second is too fast",
+        "warning: cue #1: a line has 59 characters, more than 42",
+        "error: cue #2: the duration is zero",
+        "warning: cue #3: a line has 49 characters, more than 42",
+      ]
+    );
+  }
+
+  #[test]
+  fn locations_are_ordered_like_they_are_compared() {
+    let mut locations = vec![
+      Location::Element(1),
+      Location::Span { element: 0, span: 0 },
+      Location::Element(0),
+      Location::Cue(1),
+      Location::Cue(0),
+    ];
+    locations.sort();
+    assert_eq!(
+      locations,
+      [
+        Location::Cue(0),
+        Location::Cue(1),
+        Location::Element(0),
+        Location::Span { element: 0, span: 0 },
+        Location::Element(1),
+      ]
+    );
+    assert_ne!(Location::Cue(0).cmp(&Location::Element(0)), std::cmp::Ordering::Equal);
+  }
+
+  #[cfg(feature = "srv3")]
+  #[test]
+  fn srv3_references_are_validated() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+      <head><pen id="1" b="1"/><pen id="2" rb="1"/><wp id="0" cc="10"/><ws id="0"/></head>
+      <body>
+        <w id="1" t="0" wp="0" ws="2"/>
+        <p t="0" d="5000" wp="0" ws="0"><s p="1">short</s><s p="7"> and much longer</s></p>
+        <p t="5000" d="2000"><s p="2">漢字</s><s>を読む</s></p>
+      </body>
+    </timedtext>"#;
+    let transcript: srv3::Transcript = xml.parse().unwrap();
+
+    let problems: Vec<_> =
+      transcript.validate(&Default::default()).into_iter().map(|it| (it.severity, it.location, it.problem)).collect();
+    assert_eq!(
+      problems,
+      [
+        (Severity::Error, Location::Element(0), Problem::MissingWindowStyle(2)),
+        (Severity::Warning, Location::Element(1), Problem::LineTooLong { chars: 21, max: 10 }),
+        (Severity::Error, Location::Span { element: 1, span: 1 }, Problem::MissingPen(7)),
+        (Severity::Error, Location::Element(2), Problem::UnbalancedRuby),
+      ]
+    );
+  }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// An 11 characters long identifier of a YouTube video.
+///
+/// Can be parsed either from the id itself or from any link to the video:
+///
+/// ```
+/// use youtube_captions::VideoId;
+///
+/// let id: VideoId = "https://youtu.be/JRMOIE_wAFk?t=42".parse().unwrap();
+/// assert_eq!(id, "https://www.youtube.com/watch?v=JRMOIE_wAFk&list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs".parse().unwrap());
+/// assert_eq!(id.as_str(), "JRMOIE_wAFk");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VideoId(String);
+
+impl VideoId {
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// `https://www.youtube.com/watch?v=<id>`
+  pub fn url(&self) -> String {
+    format!("https://www.youtube.com/watch?v={}", self.0)
+  }
+
+  fn is_valid(id: &str) -> bool {
+    id.len() == 11 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+  }
+
+  fn from_url(url: &Url) -> Option<String> {
+    let host = url.host_str()?.trim_start_matches("www.");
+    let mut path = url.path_segments()?.filter(|it| !it.is_empty());
+
+    match host {
+      "youtu.be" => path.next().map(str::to_owned),
+      "youtube.com" | "m.youtube.com" | "music.youtube.com" | "youtube-nocookie.com" => match path.next()? {
+        "watch" => url.query_pairs().find(|(key, _)| key == "v").map(|(_, id)| id.into_owned()),
+        "shorts" | "embed" | "live" | "v" | "e" => path.next().map(str::to_owned),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+}
+
+impl FromStr for VideoId {
+  type Err = Error;
+
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    let input = input.trim();
+    if Self::is_valid(input) {
+      return Ok(Self(input.to_owned()));
+    }
+
+    let url = if input.contains("://") { Url::parse(input) } else { Url::parse(&format!("https://{input}")) };
+
+    url
+      .ok()
+      .as_ref()
+      .and_then(Self::from_url)
+      .filter(|id| Self::is_valid(id))
+      .map(Self)
+      .ok_or_else(|| Error::InvalidVideoId(input.to_owned()))
+  }
+}
+
+impl TryFrom<String> for VideoId {
+  type Error = Error;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl From<VideoId> for String {
+  fn from(value: VideoId) -> Self {
+    value.0
+  }
+}
+
+impl From<&VideoId> for VideoId {
+  fn from(value: &VideoId) -> Self {
+    value.clone()
+  }
+}
+
+impl AsRef<str> for VideoId {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl fmt::Display for VideoId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn urls_are_parsed() {
+    let urls = [
+      "JRMOIE_wAFk",
+      " JRMOIE_wAFk\n",
+      "https://www.youtube.com/watch?v=JRMOIE_wAFk",
+      "https://www.youtube.com/watch?v=JRMOIE_wAFk&t=42s",
+      "https://www.youtube.com/watch?list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs&index=2&v=JRMOIE_wAFk",
+      "youtube.com/watch?v=JRMOIE_wAFk",
+      "http://m.youtube.com/watch?v=JRMOIE_wAFk&feature=share",
+      "https://music.youtube.com/watch?v=JRMOIE_wAFk&si=2uTlfZr4PKIKAh4h",
+      "https://youtu.be/JRMOIE_wAFk",
+      "youtu.be/JRMOIE_wAFk?t=42",
+      "https://www.youtube.com/shorts/JRMOIE_wAFk",
+      "https://www.youtube.com/embed/JRMOIE_wAFk?start=42",
+      "https://www.youtube-nocookie.com/embed/JRMOIE_wAFk",
+      "https://www.youtube.com/live/JRMOIE_wAFk?feature=shared",
+    ];
+
+    for url in urls {
+      assert_eq!(url.parse::<VideoId>().unwrap().as_str(), "JRMOIE_wAFk", "{url}");
+    }
+  }
+
+  #[test]
+  fn malformed_ids_are_rejected() {
+    let urls = [
+      "",
+      "JRMOIE_wAF",
+      "JRMOIE_wAFk1",
+      "JRMOIE wAFk",
+      "JRMOIE&wAFk",
+      "https://www.youtube.com/watch?v=JRMOIE_wAF",
+      "https://www.youtube.com/watch?list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs",
+      "https://www.youtube.com/@jetbrainstv",
+      "https://example.com/watch?v=JRMOIE_wAFk",
+      "https://youtu.be/",
+    ];
+
+    for url in urls {
+      assert!(url.parse::<VideoId>().is_err(), "{url}");
+    }
+  }
+}
@@ -1,10 +1,13 @@
-use youtube_captions::scraper::{CaptionScraper, DigestScraper};
+#![allow(dead_code)]
+
 use youtube_captions::language_tags::LanguageTag;
+use youtube_captions::{CaptionScraper, DigestScraper, VideoId};
 
 pub type Any = Result<(), Box<dyn std::error::Error>>;
 
 pub async fn with(video_id: &str, lang: &str) -> CaptionScraper {
   let scraper = DigestScraper::new(reqwest::Client::new());
+  let video_id: VideoId = video_id.parse().unwrap();
   let digest = scraper.fetch(video_id, None).await.unwrap();
   let lang = LanguageTag::parse(lang).unwrap();
   digest.captions.into_iter().find(|cap| lang.matches(&cap.lang_tag)).unwrap()
@@ -0,0 +1,321 @@
+//! Round-trips of generated documents and properties of the time conversions, all offline.
+//! The documents are written the way YouTube does, with the texts escaped twice.
+
+use proptest::prelude::*;
+use youtube_captions::time::{Duration, Timestamp};
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+/// Words joined by single spaces, as the deserializer trims the texts.
+fn text() -> impl Strategy<Value = String> {
+  prop::collection::vec("[a-zA-Z0-9'\"&<>;#é漢]{1,6}", 1..4).prop_map(|it| it.join(" "))
+}
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+fn escape_twice(text: &str) -> String {
+  escape(&escape(text))
+}
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+/// Any time, up to the saturating end of `u32` millis.
+fn millis() -> impl Strategy<Value = u32> {
+  any::<u32>()
+}
+
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+/// The document up to a random place, on a character boundary.
+fn cut(xml: &str, index: prop::sample::Index) -> &str {
+  let mut end = index.index(xml.len() + 1);
+  while !xml.is_char_boundary(end) {
+    end -= 1;
+  }
+  &xml[..end]
+}
+
+/// Properties of every format: strict parsing fails at the first warning of the lenient one, and neither broken
+/// nor arbitrary input panics on the way to an exported document. Expects `Transcript` of the format in scope.
+#[cfg(any(feature = "srv1", feature = "srv2", feature = "srv3"))]
+macro_rules! parsing_properties {
+  ($format:expr, $documents:expr) => {
+    fn check(input: &str) -> Result<(), TestCaseError> {
+      let (lenient, warnings) = Transcript::parse_lenient(input);
+      match Transcript::parse(input) {
+        Ok(strict) => prop_assert_eq!((strict, warnings.len()), (lenient, 0)),
+        Err(err) => prop_assert_eq!(warnings.first(), Some(&err)),
+      }
+
+      youtube_captions::format::Format::detect(input);
+      if let Ok(cues) = youtube_captions::cue::Cues::parse(input, $format) {
+        youtube_captions::export::to_srt(&cues);
+      }
+      Ok(())
+    }
+
+    proptest! {
+      #[test]
+      fn broken_documents_are_reported(xml in $documents, cut in any::<prop::sample::Index>()) {
+        check(super::cut(&xml, cut))?;
+      }
+
+      #[test]
+      fn any_input_is_parsed_without_panics(input in any::<String>()) {
+        check(&input)?;
+      }
+    }
+  };
+}
+
+fn secs(millis: u32) -> String {
+  format!("{}.{:03}", millis / 1000, millis % 1000)
+}
+
+/// Decimal seconds of any length, mostly too large for `u32` millis, sometimes with a dot but no fraction.
+fn secs_str() -> impl Strategy<Value = String> {
+  "[0-9]{1,30}(\\.[0-9]{0,6})?"
+}
+
+proptest! {
+  #[test]
+  fn clocks_are_parsed_back(millis in any::<u32>()) {
+    let time = Timestamp::from_millis(millis);
+    prop_assert_eq!(time.to_srt().parse::<Timestamp>().unwrap(), time);
+    prop_assert_eq!(time.to_vtt().parse::<Timestamp>().unwrap(), time);
+    prop_assert_eq!(time.to_ttml().parse::<Timestamp>().unwrap(), time);
+    prop_assert_eq!(Timestamp::from_secs_str(&secs(millis)).unwrap(), time);
+  }
+
+  #[test]
+  fn secs_are_parsed_without_overflow(secs in secs_str()) {
+    let (int, fraction) = secs.split_once('.').unwrap_or((&secs, ""));
+    let fraction = format!("{fraction:0<4}");
+    let rounded = (fraction.as_bytes()[3] >= b'5') as u128;
+    let millis = int.parse::<u128>().unwrap() * 1000 + fraction[..3].parse::<u128>().unwrap() + rounded;
+    let expected = u32::try_from(millis).ok().filter(|_| !secs.ends_with('.'));
+    prop_assert_eq!(Timestamp::from_secs_str(&secs).ok().map(Timestamp::as_millis), expected);
+  }
+
+  #[test]
+  fn conversions_keep_the_order(a in any::<u32>(), b in any::<u32>()) {
+    let (a, b) = (Timestamp::from_millis(a.min(b)), Timestamp::from_millis(a.max(b)));
+    let ass = |it: Timestamp| it.to_ass().parse::<Timestamp>().unwrap();
+    prop_assert!(ass(a) <= ass(b));
+    prop_assert!(ass(a) <= a && a - ass(a) < Duration::from_millis(10));
+    prop_assert!(a.to_vtt().len() < b.to_vtt().len() || a.to_vtt() <= b.to_vtt());
+    prop_assert!(a + (b - a) == b && a <= a + Duration::from_millis(b.as_millis()));
+  }
+}
+
+#[cfg(feature = "srv1")]
+mod srv1 {
+  use std::borrow::Cow;
+
+  use youtube_captions::format::srv1::{TextSegment, Transcript};
+
+  use super::*;
+
+  fn transcript() -> impl Strategy<Value = Transcript<'static>> {
+    let segment = (millis(), millis(), text()).prop_map(|(start, duration, value)| TextSegment {
+      start: Timestamp::from_millis(start),
+      duration: Duration::from_millis(duration),
+      value: Cow::Owned(value),
+    });
+    prop::collection::vec(segment, 0..8).prop_map(|segments| Transcript { segments })
+  }
+
+  /// Segments with times written as any digits, which don't always fit.
+  fn document() -> impl Strategy<Value = String> {
+    let segment = (secs_str(), secs_str(), text())
+      .prop_map(|(start, dur, value)| format!(r#"<text start="{start}" dur="{dur}">{}</text>"#, escape_twice(&value)));
+    prop::collection::vec(segment, 0..8).prop_map(|it| format!("<transcript>{}</transcript>", it.concat()))
+  }
+
+  fn write(transcript: &Transcript) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="utf-8" ?><transcript>"#);
+    for it in &transcript.segments {
+      let (start, dur) = (secs(it.start.as_millis()), secs(it.duration.as_millis()));
+      xml += &format!(r#"<text start="{start}" dur="{dur}">{}</text>"#, escape_twice(&it.value));
+    }
+    xml + "</transcript>"
+  }
+
+  proptest! {
+    #[test]
+    fn documents_are_parsed_back(transcript in transcript()) {
+      let xml = write(&transcript);
+      prop_assert_eq!(Transcript::parse(&xml).unwrap(), transcript);
+    }
+  }
+
+  parsing_properties!(
+    youtube_captions::format::Format::SRV1,
+    prop_oneof![transcript().prop_map(|it| write(&it)), document()]
+  );
+}
+
+#[cfg(feature = "srv2")]
+mod srv2 {
+  use youtube_captions::format::srv2::{Element, ScrollDirection, Transcript, WindowOp};
+
+  use super::*;
+
+  fn window() -> impl Strategy<Value = String> {
+    let op = prop_oneof![Just("define"), Just("set"), Just("clear"), Just("delete"), Just("unknown")];
+    let rows = prop::option::of(0..4u8).prop_map(|it| it.map_or(String::new(), |it| format!(r#" rc="{it}""#)));
+    let scroll = prop::option::of(0..2u8).prop_map(|it| it.map_or(String::new(), |it| format!(r#" sd="{it}""#)));
+    (0..3u32, op, millis(), rows, scroll)
+      .prop_map(|(id, op, t, rows, scroll)| format!(r#"<window id="{id}" op="{op}" t="{t}"{rows}{scroll}/>"#))
+  }
+
+  fn text_segment() -> impl Strategy<Value = String> {
+    (millis(), millis(), any::<bool>(), prop::option::of(0..3u32), text()).prop_map(|(t, d, append, w, text)| {
+      let append = if append { r#" append="1""# } else { "" };
+      let w = w.map_or(String::new(), |it| format!(r#" w="{it}""#));
+      format!(r#"<text t="{t}" d="{d}"{append}{w}>{}</text>"#, escape_twice(&text))
+    })
+  }
+
+  fn document() -> impl Strategy<Value = String> {
+    let element = prop_oneof![window(), text_segment()];
+    prop::collection::vec(element, 0..8).prop_map(|it| format!("<timedtext>{}</timedtext>", it.concat()))
+  }
+
+  fn write(transcript: &Transcript) -> String {
+    let mut xml = String::from("<timedtext>");
+    for it in &transcript.elements {
+      match it {
+        Element::Window(it) => {
+          let op = match it.operation {
+            WindowOp::Define => "define",
+            WindowOp::Set => "set",
+            WindowOp::Clear => "clear",
+            WindowOp::Delete => "delete",
+            WindowOp::Unknown => "unknown",
+          };
+          xml += &format!(r#"<window id="{}" op="{op}" t="{}""#, it.id, it.start.as_millis());
+          if let Some(rows) = it.rows_total {
+            xml += &format!(r#" rc="{rows}""#);
+          }
+          if let Some(scroll) = it.scroll_direction {
+            xml += if scroll == ScrollDirection::RTL { r#" sd="1""# } else { r#" sd="0""# };
+          }
+          xml += "/>";
+        }
+        Element::Text(it) => {
+          xml += &format!(r#"<text t="{}" d="{}""#, it.start.as_millis(), it.duration.as_millis());
+          if it.append {
+            xml += r#" append="1""#;
+          }
+          if let Some(w) = it.window_id {
+            xml += &format!(r#" w="{w}""#);
+          }
+          xml += &format!(">{}</text>", escape_twice(&it.value));
+        }
+      }
+    }
+    xml + "</timedtext>"
+  }
+
+  proptest! {
+    #[test]
+    fn documents_are_parsed_back(xml in document()) {
+      let transcript = Transcript::parse(&xml).unwrap();
+      let written = write(&transcript);
+      prop_assert_eq!(Transcript::parse(&written).unwrap(), transcript);
+    }
+  }
+
+  parsing_properties!(youtube_captions::format::Format::SRV2, document());
+}
+
+#[cfg(feature = "srv3")]
+mod srv3 {
+  use youtube_captions::format::srv3::{Element, Text, Transcript};
+
+  use super::*;
+
+  fn attribute<T: std::fmt::Display>(name: &'static str) -> impl Fn(Option<T>) -> String {
+    move |it| it.map_or(String::new(), |it| format!(r#" {name}="{it}""#))
+  }
+
+  fn head() -> impl Strategy<Value = String> {
+    let pen = (0..4u32, any::<bool>(), any::<bool>()).prop_map(|(id, b, i)| {
+      let (b, i) = (if b { r#" b="1""# } else { "" }, if i { r#" i="1""# } else { "" });
+      format!(r#"<pen id="{id}"{b}{i}/>"#)
+    });
+    prop::collection::vec(pen, 0..4).prop_map(|it| format!(r#"<head>{}<ws id="0"/><wp id="0"/></head>"#, it.concat()))
+  }
+
+  fn span() -> impl Strategy<Value = String> {
+    (prop::option::of(0..1000u32), prop::option::of(0..4u32), text())
+      .prop_map(|(t, p, text)| format!("<s{}{}>{}</s>", attribute("t")(t), attribute("p")(p), escape_twice(&text)))
+  }
+
+  fn segment() -> impl Strategy<Value = String> {
+    let value = prop_oneof![
+      text().prop_map(|it| escape_twice(&it)),
+      prop::collection::vec(span(), 0..4).prop_map(|it| it.concat())
+    ];
+    (millis(), prop::option::of(millis()), prop::option::of(0..4u32), value)
+      .prop_map(|(t, d, p, value)| format!(r#"<p t="{t}"{}{}>{value}</p>"#, attribute("d")(d), attribute("p")(p)))
+  }
+
+  fn document() -> impl Strategy<Value = String> {
+    let window = (0..3u32, millis()).prop_map(|(id, t)| format!(r#"<w id="{id}" t="{t}" wp="0" ws="0"/>"#));
+    let elements = prop::collection::vec(prop_oneof![segment(), window], 0..8);
+    (head(), elements)
+      .prop_map(|(head, it)| format!(r#"<timedtext format="3">{head}<body>{}</body></timedtext>"#, it.concat()))
+  }
+
+  fn write(transcript: &Transcript) -> String {
+    let mut xml = format!(r#"<timedtext format="{}"><head>"#, transcript.format_version);
+    for it in &transcript.head.pens {
+      let (b, i) = (if it.bold { r#" b="1""# } else { "" }, if it.italic { r#" i="1""# } else { "" });
+      xml += &format!(r#"<pen id="{}"{b}{i}/>"#, it.id);
+    }
+    for it in &transcript.head.window_styling {
+      xml += &format!(r#"<ws id="{}"/>"#, it.id);
+    }
+    for it in &transcript.head.window_positioning {
+      xml += &format!(r#"<wp id="{}"/>"#, it.id);
+    }
+    xml += "</head><body>";
+    for it in &transcript.body.elements {
+      match it {
+        Element::Window(it) => {
+          let (wp, ws) = (it.window_position_id, it.window_style_id);
+          xml += &format!(r#"<w id="{}" t="{}" wp="{wp}" ws="{ws}"/>"#, it.id, it.start.as_millis());
+        }
+        Element::Segment(seg) => {
+          let d = (!seg.duration.is_zero()).then(|| seg.duration.as_millis());
+          xml += &format!(r#"<p t="{}"{}{}>"#, seg.start.as_millis(), attribute("d")(d), attribute("p")(seg.pen_id));
+          for text in &seg.value {
+            match text {
+              Text::Span(it) => {
+                let t = (!it.offset.is_zero()).then(|| it.offset.as_millis());
+                xml += &format!("<s{}{}>{}</s>", attribute("t")(t), attribute("p")(it.pen_id), escape_twice(&it.value));
+              }
+              Text::Str(it) => xml += &escape_twice(it),
+            }
+          }
+          xml += "</p>";
+        }
+      }
+    }
+    xml + "</body></timedtext>"
+  }
+
+  proptest! {
+    #[test]
+    fn documents_are_parsed_back(xml in document()) {
+      let transcript = Transcript::parse(&xml).unwrap();
+      let written = write(&transcript);
+      prop_assert_eq!(Transcript::parse(&written).unwrap(), transcript);
+    }
+  }
+
+  parsing_properties!(youtube_captions::format::Format::SRV3, document());
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "srv1")]
+
 use helpers::*;
 
 mod helpers;
@@ -19,4 +21,40 @@ mod srv1 {
     insta::assert_json_snapshot!(transcript);
     Ok(())
   }
+
+  #[test]
+  fn times_are_exact() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8" ?><transcript>
+      <text start="4.333" dur="2.1">Just A Girl</text>
+    </transcript>"#;
+    let transcript: youtube_captions::format::srv1::Transcript = xml.parse().unwrap();
+
+    let seg = &transcript.segments[0];
+    assert_eq!((seg.start.as_millis(), seg.duration.as_millis(), seg.end().as_millis()), (4333, 2100, 6433));
+  }
+
+  #[test]
+  fn bad_entities_are_kept_by_lenient_parsing() {
+    use youtube_captions::format::srv1::Transcript;
+
+    let xml =
+      "<transcript>\n<text start=\"0\" dur=\"1\"> </text>\n<text start=\"1\" dur=\"1\">R&amp;B</text>\n</transcript>";
+    let err = Transcript::parse(xml).unwrap_err();
+    assert!(err.to_string().starts_with("line 3, column 1, /transcript/text[2]: "), "{err}");
+
+    let (transcript, warnings) = Transcript::parse_lenient(xml);
+    let texts: Vec<_> = transcript.segments.iter().map(|it| it.value.as_ref()).collect();
+    assert_eq!((texts, warnings), (vec!["", "R&B"], vec![err]));
+  }
+
+  #[test]
+  fn other_roots_are_rejected() {
+    use youtube_captions::format::srv1::Transcript;
+
+    let xml = r#"<timedtext><text start="0" dur="1">hi</text></timedtext>"#;
+    assert_eq!(
+      Transcript::parse(xml).unwrap_err().message,
+      "expected the root element <transcript>, found <timedtext>"
+    );
+  }
 }
@@ -1,3 +1,9 @@
+#![cfg(feature = "srv2")]
+
+use helpers::*;
+
+mod helpers;
+
 #[cfg(feature = "srv2")]
 #[tokio::test]
 async fn srv2_is_deserialized() -> Any {
@@ -5,3 +11,51 @@ async fn srv2_is_deserialized() -> Any {
   insta::assert_json_snapshot!(content);
   Ok(())
 }
+
+#[test]
+fn rolling_captions_become_pop_on_cues() {
+  use youtube_captions::format::srv2::Transcript;
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext>
+    <window id="1" op="define" t="0" rc="2" ap="7" ah="50" av="100" cc="32" sd="0" ju="2"/>
+    <text w="1" t="0" d="5000">one</text>
+    <text w="1" t="1000" d="5000" append="1"> two</text>
+    <text w="1" t="2000" d="5000">three</text>
+    <text w="1" t="3000" d="5000">four</text>
+    <window id="1" op="clear" t="4000"/>
+    <text w="1" t="4500" d="1000">five</text>
+  </timedtext>"#;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let cues = transcript.pop_on_cues();
+  let cues: Vec<_> = cues.iter().map(|it| (it.start_millis, it.end_millis(), it.text.as_str())).collect();
+  assert_eq!(
+    cues,
+    [
+      (0, 1000, "one"),
+      (1000, 2000, "one two"),
+      (2000, 3000, "one two\nthree"),
+      (3000, 4000, "three\nfour"),
+      (4500, 5500, "five")
+    ]
+  );
+}
+
+#[test]
+fn unknown_window_operations_are_ignored() {
+  use youtube_captions::format::srv2::{Element, Transcript, WindowOp};
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext>
+    <window id="1" op="define" t="0" rc="1"/>
+    <text w="1" t="0" d="3000">one</text>
+    <window id="1" op="move" t="1000" rc="2"/>
+    <text w="1" t="2000" d="3000">two</text>
+  </timedtext>"#;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let Element::Window(window) = &transcript.elements[2] else { panic!() };
+  assert_eq!(window.operation, WindowOp::Unknown);
+  let cues = transcript.pop_on_cues();
+  let cues: Vec<_> = cues.iter().map(|it| (it.start_millis, it.end_millis(), it.text.as_str())).collect();
+  assert_eq!(cues, [(0, 2000, "one"), (2000, 5000, "two")]);
+}
@@ -1,3 +1,9 @@
+#![cfg(feature = "srv3")]
+
+use helpers::*;
+
+mod helpers;
+
 #[cfg(feature = "srv3")]
 #[tokio::test]
 async fn srv3_is_deserialized() -> Any {
@@ -5,3 +11,185 @@ async fn srv3_is_deserialized() -> Any {
   insta::assert_json_snapshot!(content);
   Ok(())
 }
+
+#[test]
+fn runs_inherit_pens_and_windows() {
+  use youtube_captions::format::srv3::{HeadRef, Transcript};
+  use youtube_captions::Error;
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+    <head><pen id="1" b="1"/><pen id="2" i="1"/><wp id="0" ap="1"/><ws id="0"/></head>
+    <body>
+      <p t="1000" d="2000" p="1" wp="0"><s>bold</s><s t="500" p="2">italic</s></p>
+      <p t="3000" d="1000">plain</p>
+    </body>
+  </timedtext>"#;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let runs = transcript.runs().unwrap();
+  let styles: Vec<_> = runs.iter().map(|it| (it.start_millis, it.text, it.pen.bold, it.pen.italic)).collect();
+  assert_eq!(styles, [(1000, "bold", true, false), (1500, "italic", false, true), (3000, "plain", false, false)]);
+  assert_eq!(runs[1].separator, " ");
+  assert!(runs[0].window_position.anchor_point.is_some() && runs[2].window_position.anchor_point.is_none());
+
+  let broken: Transcript = xml.replace(r#"p="2""#, r#"p="3""#).parse().unwrap();
+  assert!(matches!(broken.runs(), Err(Error::UndefinedInHead(HeadRef::Pen(3)))));
+}
+
+#[test]
+fn unknown_pen_attributes_are_kept() {
+  use youtube_captions::format::srv3::{TextEmphasis, Transcript};
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+    <head><pen id="1" te="1" hg="1" xx="42"/></head>
+    <body><p t="0" d="1000" p="1">12</p></body>
+  </timedtext>"#;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let pen = &transcript.head.pens[0];
+  assert_eq!((pen.text_emphasis, pen.horizontal_group), (TextEmphasis::FilledCircle, true));
+  assert_eq!(pen.other.get("@xx").map(String::as_str), Some("42"));
+  let serialized = quick_xml::se::to_string_with_root("pen", pen).unwrap();
+  assert!(serialized.contains(r#"xx="42""#), "{serialized}");
+}
+
+#[test]
+fn unknown_pen_values_are_kept() {
+  use youtube_captions::format::srv3::{EdgeType, Element, RubyPart, Text, TextEmphasis, Transcript};
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+    <head><pen id="1" sz="200" et="3" te="5" rb="3"/></head>
+    <body><p t="0" d="1000"><s p="1" ac="252">word</s></p></body>
+  </timedtext>"#;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let pen = &transcript.head.pens[0];
+  assert_eq!(pen.font_size_perc, Some(200));
+  assert_eq!(pen.edge_type, EdgeType::GlowOutline);
+  assert_eq!((pen.text_emphasis, pen.ruby), (TextEmphasis::Unknown(5), RubyPart::Unknown(3)));
+  let serialized = quick_xml::se::to_string_with_root("pen", pen).unwrap();
+  assert!(serialized.contains(r#"te="5""#) && serialized.contains(r#"sz="200""#), "{serialized}");
+
+  let Element::Segment(seg) = &transcript.body.elements[0] else { panic!() };
+  let Text::Span(span) = &seg.value[0] else { panic!() };
+  assert_eq!(span.asr_confidence, Some(252));
+}
+
+#[test]
+fn pen_colors_are_typed() {
+  use youtube_captions::color::Rgba;
+  use youtube_captions::format::srv3::Transcript;
+  use youtube_captions::validate::{Problem, ValidationOptions};
+
+  let xml = r##"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+    <head><pen id="1" fc="#FFEE00" fo="128" ec="#000000"/><ws id="0" wfc="#123456"/></head>
+    <body><p t="0" d="1000" p="1">hi</p></body>
+  </timedtext>"##;
+  let transcript: Transcript = xml.parse().unwrap();
+
+  let pen = &transcript.head.pens[0];
+  assert_eq!(pen.foreground(), Rgba::opaque(0xff, 0xee, 0).with_opacity(128));
+  assert_eq!(pen.edge(), Some(Rgba::BLACK.with_opacity(128)));
+  assert_eq!(pen.background().to_ass(), "&H40080808");
+  assert_eq!(transcript.head.window_styling[0].fill().to_css(), "#12345600");
+  let serialized = quick_xml::se::to_string_with_root("pen", pen).unwrap();
+  assert!(serialized.contains(r##"fc="#ffee00" fo="128""##), "{serialized}");
+
+  for broken in [r#"bc="red""#, r##"fc="#+f+f+f""##, r#"fo="256""#] {
+    let xml = xml.replace(r##"fc="#FFEE00" fo="128""##, broken);
+    let err = Transcript::parse(&xml).unwrap_err();
+    assert!(err.message.contains("is not"), "{err}");
+
+    let (transcript, warnings) = Transcript::parse_lenient(&xml);
+    assert_eq!(warnings.len(), 1);
+    let problems: Vec<_> =
+      transcript.validate(&ValidationOptions::default()).into_iter().map(|it| it.problem).collect();
+    assert!(problems.contains(&Problem::MissingPen(1)), "{problems:?}");
+  }
+}
+
+#[test]
+fn texts_are_borrowed_unless_escaped() {
+  use std::borrow::Cow;
+  use youtube_captions::format::srv3::{Element, Text, Transcript};
+
+  let xml = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+    <head/><body><p t="0" d="1000"><s>plain</s><s t="500">it&amp;#39;s</s></p><p t="1000" d="1000">bare</p></body>
+  </timedtext>"#;
+  let transcript = Transcript::parse(xml).unwrap();
+
+  let texts: Vec<_> = transcript
+    .body
+    .elements
+    .iter()
+    .flat_map(|it| match it {
+      Element::Segment(seg) => seg.value.iter().map(|it| (it.as_ref(), matches!(**it, Cow::Borrowed(_)))).collect(),
+      Element::Window(_) => vec![],
+    })
+    .collect();
+  assert_eq!(texts, [("plain", true), ("it's", false), ("bare", true)]);
+
+  let owned: Transcript<'static> = transcript.clone().into_owned();
+  assert_eq!(owned, transcript);
+  assert!(matches!(&owned.body.elements[1], Element::Segment(seg) if matches!(seg.value[0], Text::Str(Cow::Owned(_)))));
+}
+
+#[test]
+fn errors_tell_where_they_are() {
+  use youtube_captions::format::srv3::Transcript;
+
+  let xml = "<timedtext format=\"3\"><head/><body>\n  <p t=\"0\">one</p>\n  <p t=\"soon\">two</p>\n</body></timedtext>";
+  let err = Transcript::parse(xml).unwrap_err();
+  assert_eq!((err.line, err.column, err.path.as_str()), (3, 3, "/timedtext/body[1]/p[2]/@t"));
+
+  let (transcript, warnings) = Transcript::parse_lenient(xml);
+  assert_eq!(transcript.body.elements.len(), 1);
+  assert_eq!(warnings, [err]);
+}
+
+#[test]
+fn malformed_documents_are_not_a_panic() {
+  use youtube_captions::format::srv3::{Element, Transcript};
+
+  let xml = r#"<timedtext><head><pen id="x"/></head><body><p t="0">fish &amp;chips</p><p t="1"> <s> </s></p><p"#;
+  assert!(Transcript::parse(xml).is_err());
+
+  let (transcript, warnings) = Transcript::parse_lenient(xml);
+  let texts: Vec<_> = transcript
+    .body
+    .elements
+    .iter()
+    .filter_map(|it| match it {
+      Element::Segment(seg) => Some(seg.text()),
+      Element::Window(_) => None,
+    })
+    .collect();
+  assert_eq!((transcript.format_version, texts), (3, vec!["fish &chips".into(), String::new()]));
+  // the outline is read before the elements, so its syntax error comes first
+  let paths: Vec<_> = warnings.iter().map(|it| it.path.as_str()).collect();
+  assert_eq!(
+    paths,
+    ["/timedtext/body[1]", "/timedtext/@format", "/timedtext/head[1]/pen[1]/@id", "/timedtext/body[1]/p[1]"]
+  );
+
+  for xml in ["", "<timedtext", "<timedtext format=\"3\"></body>", "\u{feff}<a>\u{e9}&#xZZ;</a>"] {
+    let _ = Transcript::parse_lenient(xml);
+    let _ = Transcript::parse(xml);
+  }
+}
+
+#[test]
+fn roots_and_sections_are_checked() {
+  use youtube_captions::format::srv3::Transcript;
+
+  let err = Transcript::parse(r#"<transcript format="3"><head/><body/></transcript>"#).unwrap_err();
+  assert_eq!(
+    (err.path.as_str(), err.message.as_str()),
+    ("/transcript", "expected the root element <timedtext>, found <transcript>")
+  );
+
+  let err = Transcript::parse(r#"<timedtext format="3"><body/></timedtext>"#).unwrap_err();
+  assert_eq!(err.message, "the <head> element is missing");
+  let (_, warnings) = Transcript::parse_lenient(r#"<timedtext format="3"><head/></timedtext>"#);
+  assert_eq!(warnings.iter().map(|it| it.message.as_str()).collect::<Vec<_>>(), ["the <body> element is missing"]);
+}